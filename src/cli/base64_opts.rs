@@ -1,10 +1,10 @@
 use crate::CmdExecutor;
 
-use super::verify_file;
+use super::{verify_file, verify_path};
 use anyhow::Ok;
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
-use std::{fmt, str::FromStr};
+use std::{fmt, path::PathBuf, str::FromStr};
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -13,6 +13,16 @@ pub enum Base64SubCommand {
     Encode(Base64EncodeOpts),
     #[command(name = "decode", about = "Decode base64")]
     Decode(Base64DecodeOpts),
+    #[command(
+        name = "datauri",
+        about = "Encode a file as a data URI, or decode one with -d"
+    )]
+    DataUri(DataUriOpts),
+    #[command(
+        name = "inspect",
+        about = "Decode base64 or hex and identify the payload by magic bytes"
+    )]
+    Inspect(InspectOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -20,8 +30,33 @@ pub struct Base64EncodeOpts {
     // "-" 表示从标准输入读取数据，stdin
     #[arg(short, long, value_parser=verify_file, help = "Input string", default_value = "-")]
     pub input: String,
-    #[arg(long, value_parser = parse_base64_format, default_value = "standard", help = "Base64 format")]
+    // "-" 表示写到标准输出，stdout
+    #[arg(short, long, help = "Output file", default_value = "-")]
+    pub output: String,
+    #[arg(long, value_parser = parse_base64_format, default_value = "standard", help = "Base64 format: standard, standard-nopad, urlsafe, urlsafe-pad, mime or pem")]
     pub format: Base64Format,
+    // 编码目录下的所有文件，每个文件写出 <原文件名>.b64，并生成 manifest.json
+    #[arg(long, value_parser = verify_path, conflicts_with_all = ["input", "output"], help = "Encode every file in a directory tree")]
+    pub dir: Option<PathBuf>,
+    // 不指定时写到源目录中，即原地编码
+    #[arg(
+        long,
+        requires = "dir",
+        help = "Output directory for --dir, defaults to the source directory"
+    )]
+    pub out_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        requires = "dir",
+        help = "Only encode files matching the glob, e.g. '**/*.png', can be repeated"
+    )]
+    pub include: Vec<String>,
+    #[arg(
+        long,
+        requires = "dir",
+        help = "Skip files matching the glob, can be repeated"
+    )]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -29,21 +64,71 @@ pub struct Base64DecodeOpts {
     // "-" 表示从标准输入读取数据，stdin
     #[arg(short, long, value_parser=verify_file, help = "Input base64 string", default_value = "-")]
     pub input: String,
-    #[arg(long, value_parser = parse_base64_format, default_value = "standard", help = "Base64 format")]
+    // 解码结果按原始字节写出，二进制数据建议写到文件
+    #[arg(short, long, help = "Output file", default_value = "-")]
+    pub output: String,
+    #[arg(long, value_parser = parse_base64_format, default_value = "standard", help = "Base64 format: standard, standard-nopad, urlsafe, urlsafe-pad, mime, pem or auto")]
     pub format: Base64Format,
+    // 按目录中的 manifest.json 解码并校验大小和 blake3 哈希，格式以 manifest 为准
+    #[arg(long, value_parser = verify_path, conflicts_with_all = ["input", "output", "format"], help = "Decode a directory encoded with --dir and verify it against manifest.json")]
+    pub dir: Option<PathBuf>,
+    #[arg(
+        long,
+        requires = "dir",
+        help = "Output directory for --dir, defaults to the source directory"
+    )]
+    pub out_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct DataUriOpts {
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser=verify_file, help = "Input file, or a data URI when decoding", default_value = "-")]
+    pub input: String,
+    // 解码时如果输出文件没有扩展名，根据 MIME 类型补上，例如 -o logo 写出 logo.png
+    #[arg(short, long, help = "Output file", default_value = "-")]
+    pub output: String,
+    #[arg(short, long, help = "Parse a data URI and write the decoded bytes")]
+    pub decode: bool,
+    // 默认根据输入文件的扩展名推断
+    #[arg(long, help = "MIME type, e.g. image/png", conflicts_with = "decode")]
+    pub mime: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct InspectOpts {
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser=verify_file, help = "Input base64 or hex string", default_value = "-")]
+    pub input: String,
+    #[arg(long, help = "Print textual content, JSON and JWT are pretty-printed")]
+    pub pretty: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Base64Format {
     Standard,
+    StandardNoPad,
+    // 保持兼容，urlsafe 不带填充
     UrlSafe,
+    UrlSafePad,
+    // 标准字符表，每 76 个字符用 CRLF 换行
+    Mime,
+    // 标准字符表，每 64 个字符换行
+    Pem,
+    // 只用于解码，自动识别字符表和填充，忽略空白字符
+    Auto,
 }
 
 impl From<Base64Format> for &'static str {
     fn from(format: Base64Format) -> Self {
         match format {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Mime => "mime",
+            Base64Format::Pem => "pem",
+            Base64Format::Auto => "auto",
         }
     }
 }
@@ -54,7 +139,12 @@ impl FromStr for Base64Format {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "mime" => Ok(Base64Format::Mime),
+            "pem" => Ok(Base64Format::Pem),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid base64 format")),
         }
     }
@@ -62,10 +152,7 @@ impl FromStr for Base64Format {
 
 impl fmt::Display for Base64Format {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Base64Format::Standard => write!(f, "standard"),
-            Base64Format::UrlSafe => write!(f, "urlsafe"),
-        }
+        write!(f, "{}", Into::<&str>::into(*self))
     }
 }
 
@@ -75,8 +162,24 @@ fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
 
 impl CmdExecutor for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let encoded = crate::process_encode(&self.input, self.format)?;
-        print!("{}", encoded);
+        if let Some(dir) = self.dir {
+            let out_dir = self.out_dir.unwrap_or_else(|| dir.clone());
+            let manifest = crate::process_encode_dir(
+                &dir,
+                &out_dir,
+                self.format,
+                &self.include,
+                &self.exclude,
+            )?;
+            eprintln!(
+                "encoded {} files, manifest written to {}",
+                manifest.files.len(),
+                out_dir.join(crate::MANIFEST_NAME).display()
+            );
+            return Ok(());
+        }
+
+        crate::process_encode(&self.input, &self.output, self.format)?;
 
         Ok(())
     }
@@ -84,8 +187,59 @@ impl CmdExecutor for Base64EncodeOpts {
 
 impl CmdExecutor for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let decoded = crate::process_decode(&self.input, self.format)?;
-        print!("{}", String::from_utf8(decoded)?);
+        if let Some(dir) = self.dir {
+            let out_dir = self.out_dir.unwrap_or_else(|| dir.clone());
+            let mismatched = crate::process_decode_dir(&dir, &out_dir)?;
+            for path in &mismatched {
+                eprintln!("MISMATCH {}", path);
+            }
+            if !mismatched.is_empty() {
+                anyhow::bail!("{} files failed integrity check", mismatched.len());
+            }
+            return Ok(());
+        }
+
+        crate::process_decode(&self.input, &self.output, self.format)?;
+
+        Ok(())
+    }
+}
+
+impl CmdExecutor for DataUriOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if self.decode {
+            let output = crate::process_datauri_decode(&self.input, &self.output)?;
+            if output != "-" {
+                eprintln!("written to {}", output);
+            }
+        } else {
+            crate::process_datauri_encode(&self.input, &self.output, self.mime.as_deref())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CmdExecutor for InspectOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let report = crate::process_inspect(&self.input)?;
+
+        let mut report = Some(&report);
+        let mut indent = 0;
+        while let Some(r) = report {
+            let pad = " ".repeat(indent);
+            println!("{}encoding: {}", pad, r.encoding);
+            println!("{}size: {} bytes", pad, r.size);
+            println!("{}type: {}", pad, r.kind);
+            for detail in &r.details {
+                println!("{}  - {}", pad, detail);
+            }
+            if let (true, Some(content)) = (self.pretty, &r.content) {
+                println!("{}", content.trim_end());
+            }
+            report = r.inner.as_deref();
+            indent += 2;
+        }
 
         Ok(())
     }
@@ -1,4 +1,5 @@
 use clap::Parser;
+use enum_dispatch::enum_dispatch;
 use std::{
     fmt::{self},
     str::FromStr,
@@ -10,9 +11,14 @@ use crate::CmdExecutor;
 use super::verify_file;
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvOpts {
-    #[arg(short, long, help = "Input CSV file path", value_parser = verify_file)]
-    pub input: String,
+    // 不带子命令时执行格式转换，例如 rcli csv -i input.csv
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+
+    #[arg(short, long, help = "Input CSV file path", value_parser = verify_file, required = true)]
+    pub input: Option<String>,
 
     // "output.json".into() 会将字符串转换为String类型
     // Option<String> 表示这个字段是可选的
@@ -30,16 +36,95 @@ pub struct CsvOpts {
 
     #[arg(long, help = "CSV file has header", default_value_t = true)]
     pub header: bool,
+
+    #[arg(long, help = "Rows per Parquet row group", default_value_t = 1024 * 1024, value_parser = clap::value_parser!(u64).range(1..))]
+    pub row_group_size: u64,
+
+    #[arg(long, help = "Parquet compression codec", value_parser = parse_compression, default_value = "snappy")]
+    pub compression: ParquetCompression,
+
+    #[arg(short, long, help = "Watch the input file and re-convert on change")]
+    pub watch: bool,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum CsvSubCommand {
+    #[command(
+        name = "sort",
+        about = "Sort a CSV file, even if it is larger than memory"
+    )]
+    Sort(CsvSortOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSortOpts {
+    #[arg(short, long, help = "Input CSV file path", value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    // 不指定时输出到标准输出
+    #[arg(short, long, help = "Output file path")]
+    pub output: Option<String>,
+
+    #[arg(
+        long,
+        help = "Columns to sort by, e.g. col1,col2",
+        value_delimiter = ',',
+        required = true
+    )]
+    pub by: Vec<String>,
+
+    #[arg(long, help = "Compare columns as numbers")]
+    pub numeric: bool,
+
+    #[arg(long, help = "Sort in descending order")]
+    pub desc: bool,
+
+    #[arg(short, long, help = "CSV delimiter", default_value_t = ',')]
+    pub delimiter: char,
+
+    #[arg(long, help = "Rows per sorted run kept in memory", default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub chunk_size: u64,
 }
 
 impl CmdExecutor for CsvOpts {
     async fn execute(self) -> anyhow::Result<()> {
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        let input = self
+            .input
+            .ok_or_else(|| anyhow::anyhow!("Input CSV file path is required"))?;
         let output = if let Some(output) = self.output.clone() {
             output
         } else {
             format!("output.{}", self.format)
         };
-        crate::process_csv(&self.input, output, self.format)
+        let opts = crate::CsvConvertOptions {
+            format: self.format,
+            delimiter: self.delimiter,
+            header: self.header,
+            row_group_size: self.row_group_size as usize,
+            compression: self.compression,
+        };
+        if self.watch {
+            return crate::process_csv_watch(&input, &output, &opts).await;
+        }
+        crate::process_csv(&input, output, &opts)
+    }
+}
+
+impl CmdExecutor for CsvSortOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let opts = crate::CsvSortOptions {
+            by: self.by,
+            numeric: self.numeric,
+            desc: self.desc,
+            delimiter: self.delimiter,
+            chunk_size: self.chunk_size as usize,
+        };
+        crate::process_csv_sort(&self.input, self.output.as_deref(), &opts)
     }
 }
 
@@ -47,6 +132,17 @@ impl CmdExecutor for CsvOpts {
 pub enum OutputFormat {
     Json,
     Yaml,
+    Csv,
+    Parquet,
+    Arrow,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Zstd,
 }
 
 pub fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
@@ -60,6 +156,9 @@ impl From<OutputFormat> for &'static str {
         match format {
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Arrow => "arrow",
         }
     }
 }
@@ -71,6 +170,9 @@ impl FromStr for OutputFormat {
         match s.to_lowercase().as_str() {
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "arrow" => Ok(OutputFormat::Arrow),
             v => anyhow::bail!("Unsupported format: {}", v),
         }
     }
@@ -87,3 +189,37 @@ impl fmt::Display for OutputFormat {
         write!(f, "{}", Into::<&str>::into(*self))
     }
 }
+
+pub fn parse_compression(compression: &str) -> Result<ParquetCompression, anyhow::Error> {
+    compression.parse()
+}
+
+impl From<ParquetCompression> for &'static str {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::Uncompressed => "uncompressed",
+            ParquetCompression::Snappy => "snappy",
+            ParquetCompression::Gzip => "gzip",
+            ParquetCompression::Zstd => "zstd",
+        }
+    }
+}
+
+impl FromStr for ParquetCompression {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uncompressed" | "none" => Ok(ParquetCompression::Uncompressed),
+            "snappy" => Ok(ParquetCompression::Snappy),
+            "gzip" => Ok(ParquetCompression::Gzip),
+            "zstd" => Ok(ParquetCompression::Zstd),
+            v => anyhow::bail!("Unsupported compression: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for ParquetCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
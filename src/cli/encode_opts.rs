@@ -0,0 +1,96 @@
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+use super::verify_file;
+use crate::CmdExecutor;
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short, long, value_parser = parse_codec, help = "Codec: hex, base32, base32-crockford, base58, base58check, ascii85, z85, percent or qp")]
+    pub codec: Codec,
+    #[arg(short, long, help = "Decode instead of encode")]
+    pub decode: bool,
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser = verify_file, help = "Input file", default_value = "-")]
+    pub input: String,
+    // "-" 表示写到标准输出，stdout
+    #[arg(short, long, help = "Output file", default_value = "-")]
+    pub output: String,
+    // 例如 --separator ":" 输出 de:ad:be:ef，解码时会自动忽略空白、: - , 等分隔符
+    #[arg(long, help = "Separator between hex bytes", default_value = "")]
+    pub separator: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    Hex,
+    // RFC 4648
+    Base32,
+    Base32Crockford,
+    // 比特币字符表
+    Base58,
+    // 末尾附加 4 字节的双 SHA256 校验
+    Base58Check,
+    Ascii85,
+    Z85,
+    Percent,
+    QuotedPrintable,
+}
+
+impl CmdExecutor for EncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_codec(
+            &self.input,
+            &self.output,
+            self.codec,
+            self.decode,
+            &self.separator,
+        )?;
+        Ok(())
+    }
+}
+
+fn parse_codec(codec: &str) -> Result<Codec, anyhow::Error> {
+    codec.parse()
+}
+
+impl From<Codec> for &'static str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Hex => "hex",
+            Codec::Base32 => "base32",
+            Codec::Base32Crockford => "base32-crockford",
+            Codec::Base58 => "base58",
+            Codec::Base58Check => "base58check",
+            Codec::Ascii85 => "ascii85",
+            Codec::Z85 => "z85",
+            Codec::Percent => "percent",
+            Codec::QuotedPrintable => "qp",
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(Codec::Hex),
+            "base32" => Ok(Codec::Base32),
+            "base32-crockford" | "crockford" => Ok(Codec::Base32Crockford),
+            "base58" => Ok(Codec::Base58),
+            "base58check" => Ok(Codec::Base58Check),
+            "ascii85" | "base85" => Ok(Codec::Ascii85),
+            "z85" => Ok(Codec::Z85),
+            "percent" | "url" => Ok(Codec::Percent),
+            "qp" | "quoted-printable" => Ok(Codec::QuotedPrintable),
+            v => anyhow::bail!("Unsupported codec: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
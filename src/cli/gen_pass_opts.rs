@@ -1,41 +1,438 @@
 use anyhow::Ok;
 use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, io, str::FromStr};
 use zxcvbn::zxcvbn;
 
-use crate::CmdExecutor;
+use super::{verify_file, OtpSubCommand, TokenOpts};
+use crate::{BreachDb, CmdExecutor, PasswordPolicy};
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct GenPassOpts {
-    #[arg(short, long, help = "Password length", default_value_t = 16)]
-    pub length: u8,
+    // 不带子命令时生成密码
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
 
-    #[arg(long, default_value_t = false)]
+    // 不指定时使用策略中的长度，默认策略为 16
+    #[arg(short, long, help = "Password length, up to 4096 [default: 16]")]
+    pub length: Option<usize>,
+
+    #[arg(long, default_value_t = false, conflicts_with = "policy")]
     pub no_uppercase: bool,
 
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, conflicts_with = "policy")]
     pub no_lowercase: bool,
 
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, conflicts_with = "policy")]
     pub no_number: bool,
 
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, conflicts_with = "policy")]
     pub no_symbol: bool,
+
+    #[arg(
+        long,
+        help = "Password policy preset (default, aws, azure-ad, pin) or a TOML/YAML policy file",
+        conflicts_with = "words"
+    )]
+    pub policy: Option<String>,
+
+    // 辅音元音交替，适合电话中念给别人，熵比随机字符低
+    #[arg(
+        long,
+        help = "Generate a pronounceable password",
+        conflicts_with = "words"
+    )]
+    pub pronounceable: bool,
+
+    // 指定单词数量时生成 diceware 口令，而不是随机字符密码
+    #[arg(long, help = "Generate a passphrase with this many words")]
+    pub words: Option<u8>,
+
+    #[arg(
+        long,
+        help = "Passphrase word separator",
+        default_value = "-",
+        requires = "words"
+    )]
+    pub separator: String,
+
+    #[arg(long, help = "Custom wordlist file, defaults to the EFF large wordlist", value_parser = verify_file, requires = "words")]
+    pub wordlist: Option<String>,
+
+    #[arg(long, help = "Capitalize each passphrase word", requires = "words")]
+    pub capitalize: bool,
+
+    #[arg(
+        long,
+        help = "Number of random digits inserted into the passphrase",
+        default_value_t = 0,
+        requires = "words"
+    )]
+    pub digits: u8,
+
+    #[arg(long, help = "Random number generator", value_parser = parse_rng, default_value = "thread")]
+    pub rng: RngSource,
+
+    // 指定后会在 length 的基础上继续增加长度，直到满足最小熵
+    #[arg(
+        long,
+        help = "Minimum password entropy in bits",
+        conflicts_with = "words"
+    )]
+    pub min_entropy: Option<f64>,
+
+    #[arg(short, long, help = "Number of passwords to generate", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+
+    #[arg(long, help = "Output format: plain, json or csv", value_parser = parse_output_format, default_value = "plain")]
+    pub output_format: GenPassOutputFormat,
+    // 生成的密码如果出现在泄露数据中会重新生成
+    #[arg(long, value_parser = verify_file, help = "Reject passwords found in this Pwned Passwords file or range directory")]
+    pub breach_db: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum GenPassSubCommand {
+    #[command(name = "check", about = "Check the strength of existing passwords")]
+    Check(GenPassCheckOpts),
+    #[command(subcommand, about = "Generate TOTP/HOTP secrets and codes")]
+    Otp(OtpSubCommand),
+    #[command(
+        name = "token",
+        about = "Generate or verify API tokens with a checksum"
+    )]
+    Token(TokenOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    // 每行一个密码，"-" 表示从标准输入读取
+    #[arg(short, long, value_parser = verify_file, help = "Password file, one per line", default_value = "-")]
+    pub input: String,
+
+    #[arg(
+        long,
+        help = "User specific words, e.g. names or emails",
+        value_delimiter = ','
+    )]
+    pub user_input: Vec<String>,
+
+    #[arg(long, help = "Output format: table or json", value_parser = parse_check_format, default_value = "table")]
+    pub format: CheckOutputFormat,
+
+    #[arg(long, help = "Exit with an error if any score is below this", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub min_score: u8,
+    #[arg(long, value_parser = verify_file, help = "Local Pwned Passwords file or range directory")]
+    pub breach_db: Option<String>,
+
+    // 默认只按行号报告，避免密码明文出现在日志中
+    #[arg(
+        long,
+        help = "Include the checked passwords in plaintext in the report"
+    )]
+    pub show_passwords: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CheckOutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GenPassOutputFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RngSource {
+    Thread,
+    Os,
 }
 
 impl CmdExecutor for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let password = crate::process_gen_pass(
-            self.length,
-            !self.no_uppercase,
-            !self.no_lowercase,
-            !self.no_number,
-            !self.no_symbol,
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        let label = if self.words.is_some() {
+            "passphrase"
+        } else {
+            "password"
+        };
+        let breach_db = self.breach_db.as_deref().map(BreachDb::open).transpose()?;
+        let records = crate::process_gen_pass_records(self.count, || {
+            self.generate_unbreached(breach_db.as_ref())
+        })?;
+
+        match self.output_format {
+            // 交互使用时每个密码后面紧跟它的强度信息
+            GenPassOutputFormat::Plain => {
+                for record in records {
+                    println!("{}: {}", label, record.password);
+                    print_strength(&record.password, record.entropy);
+                }
+            }
+            format => crate::write_gen_pass_records(&records, format, io::stdout().lock())?,
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for GenPassCheckOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let reports = crate::process_password_check(
+            &self.input,
+            &self.user_input,
+            self.breach_db.as_deref(),
+            self.show_passwords,
         )?;
 
-        println!("password: {}", password);
+        match self.format {
+            CheckOutputFormat::Table => {
+                println!(
+                    "{:<6} {:<6} {:<20} {:<10} WARNING",
+                    "LINE", "SCORE", "CRACK TIME (FAST)", "BREACHED"
+                );
+                for report in &reports {
+                    println!(
+                        "{:<6} {:<6} {:<20} {:<10} {}",
+                        report.line,
+                        report.score,
+                        report.crack_times.offline_fast_hash,
+                        report
+                            .breached
+                            .map_or("-".to_string(), |count| count.to_string()),
+                        report.warning.as_deref().unwrap_or("-")
+                    );
+                    if let Some(password) = &report.password {
+                        println!("{:<6} password: {}", "", password);
+                    }
+                    for suggestion in &report.suggestions {
+                        println!("{:<6} - {}", "", suggestion);
+                    }
+                }
+            }
+            CheckOutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            }
+        }
 
-        let estimate = zxcvbn(&password, &[]);
-        eprintln!("score: {}", estimate.score());
+        let weak = reports.iter().filter(|r| r.score < self.min_score).count();
+        if weak > 0 {
+            anyhow::bail!(
+                "{} password(s) scored below the minimum score {}",
+                weak,
+                self.min_score
+            );
+        }
+        let breached = reports
+            .iter()
+            .filter(|r| r.breached.unwrap_or(0) > 0)
+            .count();
+        if breached > 0 {
+            anyhow::bail!("{} password(s) found in the breach database", breached);
+        }
         Ok(())
     }
 }
+
+impl GenPassOpts {
+    // 最多尝试 MAX_BREACH_RETRIES 次，避免策略过弱时陷入死循环
+    fn generate_unbreached(
+        &self,
+        breach_db: Option<&BreachDb>,
+    ) -> anyhow::Result<(String, f64, String)> {
+        const MAX_BREACH_RETRIES: usize = 100;
+
+        for _ in 0..MAX_BREACH_RETRIES {
+            let generated = self.generate()?;
+            match breach_db {
+                Some(db) if db.lookup(&generated.0)? > 0 => continue,
+                _ => return Ok(generated),
+            }
+        }
+        anyhow::bail!(
+            "Every generated password was found in the breach database after {} attempts",
+            MAX_BREACH_RETRIES
+        )
+    }
+
+    fn password_policy(&self) -> anyhow::Result<PasswordPolicy> {
+        let mut policy = match &self.policy {
+            Some(name) => PasswordPolicy::from_name_or_path(name)?,
+            None => PasswordPolicy::from_flags(
+                16,
+                !self.no_uppercase,
+                !self.no_lowercase,
+                !self.no_number,
+                !self.no_symbol,
+            ),
+        };
+        if let Some(length) = self.length {
+            policy.length = length;
+        }
+        if self.pronounceable {
+            policy.pronounceable = true;
+        }
+        // 在当前长度的基础上继续增加长度，直到满足最小熵
+        if let Some(bits) = self.min_entropy {
+            policy = policy.with_min_entropy(bits)?;
+        }
+        Ok(policy)
+    }
+
+    // 返回生成的密码、熵以及所使用的策略描述
+    fn generate(&self) -> anyhow::Result<(String, f64, String)> {
+        if let Some(words) = self.words {
+            let opts = crate::PassphraseOptions {
+                words,
+                separator: self.separator.clone(),
+                wordlist: self.wordlist.clone(),
+                capitalize: self.capitalize,
+                digits: self.digits,
+                rng: self.rng,
+            };
+            let (passphrase, entropy) = crate::process_gen_passphrase(&opts)?;
+            let policy = format!(
+                "words={};separator={};wordlist={};capitalize={};digits={};rng={}",
+                words,
+                opts.separator,
+                opts.wordlist.as_deref().unwrap_or("eff_large"),
+                opts.capitalize,
+                opts.digits,
+                opts.rng
+            );
+            return Ok((passphrase, entropy, policy));
+        }
+
+        let policy = self.password_policy()?;
+        let password = crate::process_gen_pass(&policy, self.rng)?;
+        let entropy = policy.entropy();
+        let policy = format!("{};rng={}", policy, self.rng);
+        Ok((password, entropy, policy))
+    }
+}
+
+fn print_strength(password: &str, entropy: f64) {
+    let estimate = zxcvbn(password, &[]);
+    let crack_times = estimate.crack_times();
+    eprintln!("entropy: {:.1} bits", entropy);
+    eprintln!("score: {}", estimate.score());
+    eprintln!(
+        "crack time (online, throttled): {}",
+        crack_times.online_throttling_100_per_hour()
+    );
+    eprintln!(
+        "crack time (online, unthrottled): {}",
+        crack_times.online_no_throttling_10_per_second()
+    );
+    eprintln!(
+        "crack time (offline, slow hash): {}",
+        crack_times.offline_slow_hashing_1e4_per_second()
+    );
+    eprintln!(
+        "crack time (offline, fast hash): {}",
+        crack_times.offline_fast_hashing_1e10_per_second()
+    );
+}
+
+pub(super) fn parse_rng(rng: &str) -> Result<RngSource, anyhow::Error> {
+    rng.parse()
+}
+
+impl From<RngSource> for &'static str {
+    fn from(rng: RngSource) -> Self {
+        match rng {
+            RngSource::Thread => "thread",
+            RngSource::Os => "os",
+        }
+    }
+}
+
+impl FromStr for RngSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "thread" => Ok(RngSource::Thread),
+            "os" => Ok(RngSource::Os),
+            v => anyhow::bail!("Unsupported rng: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for RngSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn parse_output_format(format: &str) -> Result<GenPassOutputFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<GenPassOutputFormat> for &'static str {
+    fn from(format: GenPassOutputFormat) -> Self {
+        match format {
+            GenPassOutputFormat::Plain => "plain",
+            GenPassOutputFormat::Json => "json",
+            GenPassOutputFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for GenPassOutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(GenPassOutputFormat::Plain),
+            "json" => Ok(GenPassOutputFormat::Json),
+            "csv" => Ok(GenPassOutputFormat::Csv),
+            v => anyhow::bail!("Unsupported output format: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for GenPassOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn parse_check_format(format: &str) -> Result<CheckOutputFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<CheckOutputFormat> for &'static str {
+    fn from(format: CheckOutputFormat) -> Self {
+        match format {
+            CheckOutputFormat::Table => "table",
+            CheckOutputFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for CheckOutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(CheckOutputFormat::Table),
+            "json" => Ok(CheckOutputFormat::Json),
+            v => anyhow::bail!("Unsupported output format: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for CheckOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
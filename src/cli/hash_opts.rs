@@ -0,0 +1,204 @@
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use super::{verify_file, verify_path};
+use crate::{CheckStatus, CmdExecutor};
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct HashOpts {
+    // 不带子命令时计算文件的摘要
+    #[command(subcommand)]
+    pub cmd: Option<HashSubCommand>,
+
+    // "-" 表示从标准输入读取数据，stdin；不存在的文件在计算时报错，不影响其他文件
+    #[arg(help = "Files to hash", default_value = "-")]
+    pub files: Vec<String>,
+
+    #[arg(short, long, help = "Algorithm: blake3, sha1, sha224, sha256, sha384, sha512, sha3-256, sha3-512, md5 or crc32", value_parser = parse_hash_algorithm, default_value = "sha256")]
+    pub algorithm: HashAlgorithm,
+
+    // 和 sha256sum -c 一样，文件名相对于当前目录
+    #[arg(short, long, value_parser = verify_file, conflicts_with = "files", help = "Verify the digests listed in a sha256sum style file")]
+    pub check: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        requires = "check",
+        help = "Don't print OK for each verified file"
+    )]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum HashSubCommand {
+    #[command(about = "Build a blake3 Merkle manifest of a directory")]
+    Tree(HashTreeOpts),
+    #[command(about = "Report files added, removed or modified since a tree manifest was built")]
+    VerifyTree(HashVerifyTreeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct HashTreeOpts {
+    #[arg(value_parser = verify_path, help = "Directory to hash")]
+    pub dir: PathBuf,
+    // "-" 表示写到标准输出，stdout
+    #[arg(short, long, help = "Output manifest file", default_value = "-")]
+    pub output: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct HashVerifyTreeOpts {
+    #[arg(value_parser = verify_path, help = "Directory to verify")]
+    pub dir: PathBuf,
+    #[arg(short, long, value_parser = verify_file, help = "Manifest written by hash tree")]
+    pub manifest: String,
+    // manifest 和目录放在一起时可能被一起篡改，可信的根哈希需要单独保存
+    #[arg(long, help = "Expected root hash, kept apart from the manifest")]
+    pub root: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_256,
+    Sha3_512,
+    Md5,
+    Crc32,
+    Blake3,
+}
+
+impl CmdExecutor for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        let Some(sums) = self.check else {
+            // 和 sha256sum 一样，读不了的文件报错后继续处理其余文件
+            let mut unreadable = 0;
+            for file in &self.files {
+                match crate::process_hash(file, self.algorithm) {
+                    Ok(digest) => println!("{}", crate::format_sum_line(&digest, file)),
+                    Err(e) => {
+                        unreadable += 1;
+                        eprintln!("{}: {}", file, e);
+                    }
+                }
+            }
+            if unreadable > 0 {
+                anyhow::bail!("{} files could not be read", unreadable);
+            }
+            return Ok(());
+        };
+
+        let results = crate::process_hash_check(&sums, self.algorithm)?;
+        let (mut failed, mut unreadable) = (0, 0);
+        for result in &results {
+            match result.status {
+                CheckStatus::Ok if !self.quiet => println!("{}: OK", result.path),
+                CheckStatus::Ok => {}
+                CheckStatus::Failed => {
+                    failed += 1;
+                    println!("{}: FAILED", result.path);
+                }
+                CheckStatus::Unreadable => {
+                    unreadable += 1;
+                    println!("{}: FAILED open or read", result.path);
+                }
+            }
+        }
+        if unreadable > 0 {
+            eprintln!("WARNING: {} listed files could not be read", unreadable);
+        }
+        if failed > 0 {
+            eprintln!("WARNING: {} computed checksums did NOT match", failed);
+        }
+        if failed + unreadable > 0 {
+            anyhow::bail!("Checksum verification failed");
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for HashTreeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let manifest = crate::process_hash_tree(&self.dir, &self.output)?;
+        eprintln!("{} files, root {}", manifest.files.len(), manifest.root);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for HashVerifyTreeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let diff = crate::process_verify_tree(&self.dir, &self.manifest, self.root.as_deref())?;
+        for path in &diff.added {
+            println!("added: {}", path);
+        }
+        for path in &diff.removed {
+            println!("removed: {}", path);
+        }
+        for path in &diff.modified {
+            println!("modified: {}", path);
+        }
+        if !diff.is_empty() {
+            anyhow::bail!("Directory does not match the manifest");
+        }
+        println!("OK root {}", diff.root);
+        Ok(())
+    }
+}
+
+fn parse_hash_algorithm(algorithm: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha224 => "sha224",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Sha3_512 => "sha3-512",
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha224" => Ok(HashAlgorithm::Sha224),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha384" => Ok(HashAlgorithm::Sha384),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "sha3-512" => Ok(HashAlgorithm::Sha3_512),
+            "md5" => Ok(HashAlgorithm::Md5),
+            "crc32" => Ok(HashAlgorithm::Crc32),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            v => anyhow::bail!("Unsupported hash algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
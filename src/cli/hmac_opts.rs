@@ -0,0 +1,255 @@
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, fs, str::FromStr};
+
+use super::verify_file;
+use crate::CmdExecutor;
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum HmacSubCommand {
+    #[command(about = "Compute the HMAC of a file or stdin")]
+    Sign(HmacSignOpts),
+    #[command(about = "Verify an HMAC in constant time")]
+    Verify(HmacVerifyOpts),
+    #[command(about = "Verify a GitHub or Stripe webhook signature header")]
+    Webhook(HmacWebhookOpts),
+}
+
+// 密钥可以直接给出，也可以从文件读取，避免出现在 shell 历史中
+#[derive(Debug, Clone, Parser)]
+#[group(required = true, multiple = false)]
+pub struct HmacKey {
+    #[arg(short, long, help = "Secret key")]
+    pub key: Option<String>,
+    #[arg(long, value_parser = verify_file, help = "File containing the secret key")]
+    pub key_file: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct HmacSignOpts {
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser = verify_file, help = "Input file", default_value = "-")]
+    pub input: String,
+    #[command(flatten)]
+    pub key: HmacKey,
+    #[arg(short, long, help = "Hash algorithm: sha1, sha256, sha384 or sha512", value_parser = parse_hmac_algorithm, default_value = "sha256")]
+    pub algorithm: HmacAlgorithm,
+    #[arg(long, help = "Signature encoding: hex or base64", value_parser = parse_signature_encoding, default_value = "hex")]
+    pub encoding: SignatureEncoding,
+}
+
+#[derive(Debug, Parser)]
+pub struct HmacVerifyOpts {
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser = verify_file, help = "Input file", default_value = "-")]
+    pub input: String,
+    #[command(flatten)]
+    pub key: HmacKey,
+    #[arg(short, long, help = "Hash algorithm: sha1, sha256, sha384 or sha512", value_parser = parse_hmac_algorithm, default_value = "sha256")]
+    pub algorithm: HmacAlgorithm,
+    #[arg(long, help = "Signature encoding: hex or base64", value_parser = parse_signature_encoding, default_value = "hex")]
+    pub encoding: SignatureEncoding,
+    #[arg(short, long, help = "Expected signature")]
+    pub signature: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct HmacWebhookOpts {
+    // 必须是收到的原始请求体，重新格式化过的 JSON 无法通过校验
+    #[arg(short, long, value_parser = verify_file, help = "Raw request body", default_value = "-")]
+    pub input: String,
+    #[command(flatten)]
+    pub key: HmacKey,
+    #[arg(long, help = "Webhook provider: github or stripe", value_parser = parse_webhook_provider)]
+    pub provider: WebhookProvider,
+    // GitHub 是 X-Hub-Signature-256，Stripe 是 Stripe-Signature
+    #[arg(long, help = "Value of the signature header")]
+    pub header: String,
+    // 只用于 Stripe，0 表示不检查时间戳
+    #[arg(
+        long,
+        help = "Maximum age of a Stripe timestamp in seconds",
+        default_value_t = 300
+    )]
+    pub tolerance: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HmacAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SignatureEncoding {
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WebhookProvider {
+    Github,
+    Stripe,
+}
+
+impl HmacKey {
+    // 去掉密钥文件末尾的换行，编辑器保存时通常会自动加上
+    fn load(&self) -> anyhow::Result<Vec<u8>> {
+        match (&self.key, &self.key_file) {
+            (Some(key), _) => Ok(key.as_bytes().to_vec()),
+            (None, Some(path)) => {
+                let mut key = fs::read(path)?;
+                while matches!(key.last(), Some(b'\n' | b'\r')) {
+                    key.pop();
+                }
+                Ok(key)
+            }
+            (None, None) => anyhow::bail!("Either --key or --key-file is required"),
+        }
+    }
+}
+
+impl CmdExecutor for HmacSignOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = self.key.load()?;
+        let sig = crate::process_hmac_sign(&self.input, &key, self.algorithm, self.encoding)?;
+        println!("{}", sig);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for HmacVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = self.key.load()?;
+        if crate::process_hmac_verify(
+            &self.input,
+            &key,
+            self.algorithm,
+            self.encoding,
+            &self.signature,
+        )? {
+            println!("valid");
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid signature")
+        }
+    }
+}
+
+impl CmdExecutor for HmacWebhookOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = self.key.load()?;
+        if crate::process_webhook_verify(
+            &self.input,
+            &key,
+            self.provider,
+            &self.header,
+            self.tolerance,
+        )? {
+            println!("valid");
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid webhook signature")
+        }
+    }
+}
+
+fn parse_hmac_algorithm(algorithm: &str) -> Result<HmacAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+fn parse_signature_encoding(encoding: &str) -> Result<SignatureEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+fn parse_webhook_provider(provider: &str) -> Result<WebhookProvider, anyhow::Error> {
+    provider.parse()
+}
+
+impl From<HmacAlgorithm> for &'static str {
+    fn from(algorithm: HmacAlgorithm) -> Self {
+        match algorithm {
+            HmacAlgorithm::Sha1 => "sha1",
+            HmacAlgorithm::Sha256 => "sha256",
+            HmacAlgorithm::Sha384 => "sha384",
+            HmacAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for HmacAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(HmacAlgorithm::Sha1),
+            "sha256" => Ok(HmacAlgorithm::Sha256),
+            "sha384" => Ok(HmacAlgorithm::Sha384),
+            "sha512" => Ok(HmacAlgorithm::Sha512),
+            v => anyhow::bail!("Unsupported HMAC algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for HmacAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<SignatureEncoding> for &'static str {
+    fn from(encoding: SignatureEncoding) -> Self {
+        match encoding {
+            SignatureEncoding::Hex => "hex",
+            SignatureEncoding::Base64 => "base64",
+        }
+    }
+}
+
+impl FromStr for SignatureEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(SignatureEncoding::Hex),
+            "base64" => Ok(SignatureEncoding::Base64),
+            v => anyhow::bail!("Unsupported signature encoding: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<WebhookProvider> for &'static str {
+    fn from(provider: WebhookProvider) -> Self {
+        match provider {
+            WebhookProvider::Github => "github",
+            WebhookProvider::Stripe => "stripe",
+        }
+    }
+}
+
+impl FromStr for WebhookProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "github" => Ok(WebhookProvider::Github),
+            "stripe" => Ok(WebhookProvider::Stripe),
+            v => anyhow::bail!("Unsupported webhook provider: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for WebhookProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
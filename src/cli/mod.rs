@@ -1,16 +1,23 @@
 mod base64_opts;
 mod csv_opts;
+mod encode_opts;
 mod gen_pass_opts;
+mod hash_opts;
+mod hmac_opts;
 mod http_opts;
 mod jwt_opts;
+mod otp_opts;
 mod text_opts;
+mod token_opts;
+mod transform_opts;
 
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
 use std::path::{Path, PathBuf};
 
 pub use self::{
-    base64_opts::*, csv_opts::*, gen_pass_opts::*, http_opts::*, jwt_opts::*, text_opts::*,
+    base64_opts::*, csv_opts::*, encode_opts::*, gen_pass_opts::*, hash_opts::*, hmac_opts::*,
+    http_opts::*, jwt_opts::*, otp_opts::*, text_opts::*, token_opts::*, transform_opts::*,
 };
 
 #[derive(Debug, Parser)]
@@ -29,6 +36,23 @@ pub enum SubCommand {
     GenPass(GenPassOpts),
     #[command(subcommand, about = "Base64 encode/decode")]
     Base64(Base64SubCommand),
+    #[command(
+        name = "encode",
+        about = "Encode/decode hex, base32, base58, base85, percent or quoted-printable"
+    )]
+    Encode(EncodeOpts),
+    #[command(
+        name = "transform",
+        about = "Chain codecs, compression, hashes and text sign/encrypt over one stream"
+    )]
+    Transform(TransformOpts),
+    #[command(
+        name = "hash",
+        about = "Compute or check blake3, SHA-2, SHA-3, MD5 or CRC32 digests"
+    )]
+    Hash(HashOpts),
+    #[command(subcommand, about = "HMAC sign/verify and webhook signature checks")]
+    Hmac(HmacSubCommand),
     #[command(subcommand, about = "Text sign/verify")]
     Text(TextSubCommand),
     #[command(subcommand, about = "HTTP serve")]
@@ -0,0 +1,174 @@
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+use super::{parse_rng, RngSource};
+use crate::{CmdExecutor, OtpOptions};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a new base32 secret and otpauth:// URI")]
+    New(OtpNewOpts),
+    #[command(about = "Compute the current TOTP code, or a HOTP code with --counter")]
+    Code(OtpCodeOpts),
+    #[command(about = "Verify a TOTP code, or a HOTP code with --counter")]
+    Verify(OtpVerifyOpts),
+}
+
+// 认证器 App 普遍只支持 6 位、30 秒、SHA1，修改前确认目标 App 是否支持
+#[derive(Debug, Clone, Copy, Parser)]
+pub struct OtpParams {
+    #[arg(long, help = "Number of digits in a code", default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    #[arg(long, help = "TOTP time step in seconds", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub period: u64,
+    #[arg(long, help = "HMAC algorithm: sha1, sha256 or sha512", value_parser = parse_otp_algorithm, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpNewOpts {
+    #[arg(long, help = "Account name, e.g. an email address")]
+    pub account: String,
+    #[arg(long, help = "Issuer shown in the authenticator app")]
+    pub issuer: Option<String>,
+    #[arg(long, help = "Secret size in bytes", default_value_t = 20, value_parser = clap::value_parser!(u32).range(10..=64))]
+    pub bytes: u32,
+    // 指定初始计数器时生成 HOTP URI
+    #[arg(long, help = "Initial counter, generates a HOTP URI instead of TOTP")]
+    pub counter: Option<u64>,
+    #[arg(long, help = "Print the URI as a QR code in the terminal")]
+    pub qr: bool,
+    #[arg(long, help = "Random number generator", value_parser = parse_rng, default_value = "os")]
+    pub rng: RngSource,
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpCodeOpts {
+    #[arg(short, long, help = "Base32 secret")]
+    pub secret: String,
+    #[arg(long, help = "HOTP counter, computes a TOTP code when omitted")]
+    pub counter: Option<u64>,
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    #[arg(short, long, help = "Base32 secret")]
+    pub secret: String,
+    #[arg(long, help = "Code to verify")]
+    pub code: String,
+    #[arg(long, help = "HOTP counter, verifies a TOTP code when omitted")]
+    pub counter: Option<u64>,
+    // TOTP 前后各允许 window 个时间步，HOTP 向后查找 window 个计数器
+    #[arg(
+        long,
+        help = "Number of steps accepted around the current one",
+        default_value_t = 1
+    )]
+    pub window: u64,
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<OtpParams> for OtpOptions {
+    fn from(params: OtpParams) -> Self {
+        Self {
+            digits: params.digits,
+            period: params.period,
+            algorithm: params.algorithm,
+        }
+    }
+}
+
+impl CmdExecutor for OtpNewOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let opts = self.params.into();
+        let secret = crate::process_otp_secret(self.bytes as usize, self.rng);
+        let uri = crate::process_otp_uri(
+            &secret,
+            &self.account,
+            self.issuer.as_deref(),
+            self.counter,
+            &opts,
+        );
+
+        println!("secret: {}", secret);
+        println!("uri: {}", uri);
+        if self.qr {
+            println!("{}", crate::process_otp_qr(&uri)?);
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpCodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let code = crate::process_otp_code(&self.secret, self.counter, &self.params.into())?;
+        println!("{}", code);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let matched = crate::process_otp_verify(
+            &self.secret,
+            self.code.trim(),
+            self.counter,
+            self.window,
+            &self.params.into(),
+        )?;
+        match matched {
+            Some(offset) => {
+                println!("valid (offset {})", offset);
+                Ok(())
+            }
+            None => anyhow::bail!("Invalid code"),
+        }
+    }
+}
+
+fn parse_otp_algorithm(algorithm: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            v => anyhow::bail!("Unsupported OTP algorithm: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -53,22 +53,40 @@ pub struct TextKeyGenerateOpts {
 
 #[derive(Debug, Parser)]
 pub struct TextEncryptOpts {
-    // #[arg(short, long, value_parser=verify_file, default_value = "-")]
-    // pub input: String,
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser=verify_file, default_value = "-")]
+    pub input: String,
+    // 密文为 raw 时建议写到文件
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
     #[arg(short, long, value_parser=verify_file)]
     pub key: String,
-    #[arg(short, long, value_parser=verify_file)]
-    pub nonce: String,
+    #[arg(long, default_value = "base64", value_parser = parse_cipher_encoding, help = "Ciphertext encoding: base64 or raw")]
+    pub encoding: CipherEncoding,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextDecryptOpts {
-    // #[arg(short, long, value_parser=verify_file, default_value = "-")]
-    // pub input: String,
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser=verify_file, default_value = "-")]
+    pub input: String,
+    // 解密结果按原始字节写出，二进制数据建议写到文件
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
     #[arg(short, long, value_parser=verify_file)]
     pub key: String,
-    #[arg(short, long, value_parser=verify_file)]
-    pub nonce: String,
+    // 只用于解密旧版本使用固定 nonce 加密的数据
+    #[arg(short, long, value_parser=verify_file, help = "Nonce file for data encrypted by older versions")]
+    pub nonce: Option<String>,
+    #[arg(long, default_value = "base64", value_parser = parse_cipher_encoding, help = "Ciphertext encoding: base64 or raw")]
+    pub encoding: CipherEncoding,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CipherEncoding {
+    // URL 安全字符表，不带填充
+    Base64,
+    Raw,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -115,6 +133,37 @@ fn parse_format(format: &str) -> Result<TextSignFormat, anyhow::Error> {
     format.parse()
 }
 
+impl From<CipherEncoding> for &'static str {
+    fn from(encoding: CipherEncoding) -> Self {
+        match encoding {
+            CipherEncoding::Base64 => "base64",
+            CipherEncoding::Raw => "raw",
+        }
+    }
+}
+
+impl FromStr for CipherEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "base64" => Ok(CipherEncoding::Base64),
+            "raw" => Ok(CipherEncoding::Raw),
+            v => anyhow::bail!("Unsupported cipher encoding: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for CipherEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn parse_cipher_encoding(encoding: &str) -> Result<CipherEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
 impl CmdExecutor for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
         crate::process_text_sign(&self.input, &self.key, self.format)?;
@@ -149,12 +198,9 @@ impl CmdExecutor for TextKeyGenerateOpts {
                 fs::write(name, &key[1]).await?;
             }
             TextSignFormat::ChaChaPoly => {
-                // 保存到文件
+                // 保存到文件，nonce 在每次加密时随机生成
                 let name = self.output.join("chacha_poly.txt");
                 fs::write(name, &key[0]).await?;
-
-                let name = self.output.join("chacha_poly.nonce");
-                fs::write(name.join("name"), &key[1]).await?;
             }
         }
 
@@ -164,16 +210,20 @@ impl CmdExecutor for TextKeyGenerateOpts {
 
 impl CmdExecutor for TextEncryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let encrypted = crate::process_text_encrypt(&self.key, &self.nonce)?;
-        println!("{}", encrypted);
+        crate::process_text_encrypt(&self.input, &self.output, &self.key, self.encoding)?;
         Ok(())
     }
 }
 
 impl CmdExecutor for TextDecryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let decrypted = crate::process_text_decrypt(&self.key, &self.nonce)?;
-        println!("{}", decrypted);
+        crate::process_text_decrypt(
+            &self.input,
+            &self.output,
+            &self.key,
+            self.nonce.as_deref(),
+            self.encoding,
+        )?;
         Ok(())
     }
 }
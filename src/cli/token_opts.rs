@@ -0,0 +1,120 @@
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+use super::{parse_rng, RngSource};
+use crate::{CmdExecutor, TokenOptions};
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct TokenOpts {
+    // 不带子命令时生成令牌
+    #[command(subcommand)]
+    pub cmd: Option<TokenSubCommand>,
+
+    #[arg(long, help = "Token prefix, e.g. rcli_live_", default_value = "rcli_")]
+    pub prefix: String,
+
+    #[arg(short, long, help = "Length of the random part", default_value_t = 30, value_parser = clap::value_parser!(u32).range(16..=256))]
+    pub length: u32,
+
+    #[arg(long, help = "Alphabet: base62, base58, hex or base64url", value_parser = parse_token_alphabet, default_value = "base62")]
+    pub alphabet: TokenAlphabet,
+
+    #[arg(long, help = "Random number generator", value_parser = parse_rng, default_value = "os")]
+    pub rng: RngSource,
+
+    #[arg(short, long, help = "Number of tokens to generate", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum TokenSubCommand {
+    #[command(about = "Verify the checksum of a token offline")]
+    Verify(TokenVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct TokenVerifyOpts {
+    #[arg(help = "Token to verify")]
+    pub token: String,
+
+    #[arg(long, help = "Alphabet the token was generated with", value_parser = parse_token_alphabet, default_value = "base62")]
+    pub alphabet: TokenAlphabet,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenAlphabet {
+    Base62,
+    Base58,
+    Hex,
+    Base64Url,
+}
+
+impl CmdExecutor for TokenOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        let opts = TokenOptions {
+            prefix: self.prefix,
+            length: self.length as usize,
+            alphabet: self.alphabet,
+            rng: self.rng,
+        };
+        for _ in 0..self.count {
+            let (token, entropy) = crate::process_token_generate(&opts)?;
+            println!("{}", token);
+            eprintln!("entropy: {:.1} bits", entropy);
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for TokenVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if crate::process_token_verify(&self.token, self.alphabet)? {
+            println!("valid");
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid token checksum")
+        }
+    }
+}
+
+fn parse_token_alphabet(alphabet: &str) -> Result<TokenAlphabet, anyhow::Error> {
+    alphabet.parse()
+}
+
+impl From<TokenAlphabet> for &'static str {
+    fn from(alphabet: TokenAlphabet) -> Self {
+        match alphabet {
+            TokenAlphabet::Base62 => "base62",
+            TokenAlphabet::Base58 => "base58",
+            TokenAlphabet::Hex => "hex",
+            TokenAlphabet::Base64Url => "base64url",
+        }
+    }
+}
+
+impl FromStr for TokenAlphabet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "base62" => Ok(TokenAlphabet::Base62),
+            "base58" => Ok(TokenAlphabet::Base58),
+            "hex" => Ok(TokenAlphabet::Hex),
+            "base64url" => Ok(TokenAlphabet::Base64Url),
+            v => anyhow::bail!("Unsupported token alphabet: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for TokenAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -0,0 +1,86 @@
+use clap::Parser;
+use std::str::FromStr;
+
+use super::{verify_file, Base64Format, Codec, HashAlgorithm, TextSignFormat};
+use crate::CmdExecutor;
+
+#[derive(Debug, Parser)]
+pub struct TransformOpts {
+    // "-" 表示从标准输入读取数据，stdin
+    #[arg(short, long, value_parser = verify_file, help = "Input file", default_value = "-")]
+    pub input: String,
+    // "-" 表示写到标准输出，stdout
+    #[arg(short, long, help = "Output file", default_value = "-")]
+    pub output: String,
+    // 例如 --chain 'gunzip,base64-decode,hex-encode'，按顺序依次处理；
+    // 按逗号拆分阶段，所以密钥文件路径中不能包含逗号
+    #[arg(
+        long,
+        value_delimiter = ',',
+        required = true,
+        value_parser = parse_stage,
+        help = "Comma separated stages: <codec>-encode[:sep], <codec>-decode, base64-encode[:format], base64-decode[:format], gzip, gunzip, zstd, unzstd, <hash algorithm>, sign-blake3:<key>, sign-ed25519:<key>, encrypt:<key>, decrypt:<key> (key paths must not contain ',')"
+    )]
+    pub chain: Vec<Stage>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Stage {
+    Encode(Codec, String),
+    Decode(Codec),
+    Base64Encode(Base64Format),
+    Base64Decode(Base64Format),
+    Gzip,
+    Gunzip,
+    Zstd,
+    Unzstd,
+    // 输出原始摘要字节
+    Hash(HashAlgorithm),
+    // 签名格式和密钥文件
+    Sign(TextSignFormat, String),
+    // 密钥文件，输出带随机 nonce 的信封
+    Encrypt(String),
+    Decrypt(String),
+}
+
+impl CmdExecutor for TransformOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_transform(&self.input, &self.output, &self.chain)?;
+        Ok(())
+    }
+}
+
+fn parse_stage(stage: &str) -> Result<Stage, anyhow::Error> {
+    stage.parse()
+}
+
+impl FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name.trim().to_lowercase(), Some(arg)),
+            None => (s.trim().to_lowercase(), None),
+        };
+        let stage = match (name.as_str(), arg) {
+            ("gzip", None) => Stage::Gzip,
+            ("gunzip", None) => Stage::Gunzip,
+            ("zstd", None) => Stage::Zstd,
+            ("unzstd", None) => Stage::Unzstd,
+            ("base64-encode", format) => Stage::Base64Encode(format.unwrap_or("standard").parse()?),
+            ("base64-decode", format) => Stage::Base64Decode(format.unwrap_or("auto").parse()?),
+            ("sign-blake3", Some(key)) => Stage::Sign(TextSignFormat::Blake3, key.to_string()),
+            ("sign-ed25519", Some(key)) => Stage::Sign(TextSignFormat::Ed25519, key.to_string()),
+            ("encrypt", Some(key)) => Stage::Encrypt(key.to_string()),
+            ("decrypt", Some(key)) => Stage::Decrypt(key.to_string()),
+            (name, None) if name.parse::<HashAlgorithm>().is_ok() => Stage::Hash(name.parse()?),
+            (name, separator) => match (name.strip_suffix("-encode"), name.strip_suffix("-decode"))
+            {
+                (Some(codec), _) => Stage::Encode(codec.parse()?, separator.unwrap_or("").into()),
+                (_, Some(codec)) if separator.is_none() => Stage::Decode(codec.parse()?),
+                _ => anyhow::bail!("Unsupported transform stage: {}", s),
+            },
+        };
+        Ok(stage)
+    }
+}
@@ -1,47 +1,476 @@
 use anyhow::Result;
 use base64::{
-    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    alphabet,
+    engine::{
+        general_purpose::{self, STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+        DecodePaddingMode, GeneralPurpose,
+    },
+    read::DecoderReader,
+    write::EncoderWriter,
     Engine as _,
 };
+use percent_encoding::percent_decode_str;
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use super::codec::{BlockCodec, Blocked, StreamCodec};
+use crate::{get_reader, get_writer, Base64Format};
 
-use crate::{read_data, Base64Format};
+// 转换管道中每次处理的块大小，必须是 3 和 4 的倍数才不会在中间产生填充
+const STAGE_BLOCK: usize = 3 * 4 * 1024;
 
-pub fn process_encode(input: &str, format: Base64Format) -> Result<String> {
-    let buffer = read_data(input)?;
+// 宽松解码使用标准字符表，URL 安全字符在读取时转换，有没有填充都接受
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    general_purpose::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+pub fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard | Base64Format::Mime | Base64Format::Pem => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE_NO_PAD,
+        Base64Format::UrlSafePad => &URL_SAFE,
+        Base64Format::Auto => &LENIENT,
+    }
+}
+
+// 需要按行折叠的格式返回每行的字符数和换行符
+fn line_wrap(format: Base64Format) -> Option<(usize, &'static [u8])> {
+    match format {
+        // RFC 2045
+        Base64Format::Mime => Some((76, b"\r\n")),
+        // RFC 7468
+        Base64Format::Pem => Some((64, b"\n")),
+        _ => None,
+    }
+}
+
+// 边读边编码，内存占用和输入大小无关，返回读取的字节数
+pub fn process_encode(input: &str, output: &str, format: Base64Format) -> Result<u64> {
+    if let Base64Format::Auto = format {
+        anyhow::bail!("Base64 format auto is only supported when decoding");
+    }
+    let mut reader = get_reader(input)?;
+    // 不需要折叠的格式所有字符都写在同一行
+    let (width, newline) = line_wrap(format).unwrap_or((usize::MAX, b""));
+    let mut writer = LineWrap::new(get_writer(output)?, width, newline);
+
+    let mut encoder = EncoderWriter::new(&mut writer, engine(format));
+    let size = io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?;
+    drop(encoder);
+    writer.finish()?;
+
+    Ok(size)
+}
 
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(&buffer),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buffer),
+// 解码结果按原始字节写出，可以是任意二进制数据，返回写出的字节数
+pub fn process_decode(input: &str, output: &str, format: Base64Format) -> Result<u64> {
+    let reader = Sanitize {
+        inner: get_reader(input)?,
+        lenient: matches!(format, Base64Format::Auto),
     };
-    Ok(encoded)
+    let mut writer = get_writer(output)?;
+
+    let mut decoder = DecoderReader::new(reader, engine(format));
+    let size = io::copy(&mut decoder, &mut writer)?;
+    writer.flush()?;
+
+    Ok(size)
+}
+
+// 跳过换行等空白字符，例如 echo 追加的换行或按行折叠的编码
+// 宽松模式下把 URL 安全字符表的 - 和 _ 转换为标准字符表的 + 和 /
+struct Sanitize<R> {
+    inner: R,
+    lenient: bool,
+}
+
+impl<R: Read> Read for Sanitize<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                if let Some(c) = sanitize(buf[i], self.lenient) {
+                    buf[len] = c;
+                    len += 1;
+                }
+            }
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+fn sanitize(c: u8, lenient: bool) -> Option<u8> {
+    match c {
+        c if c.is_ascii_whitespace() => None,
+        b'-' if lenient => Some(b'+'),
+        b'_' if lenient => Some(b'/'),
+        c => Some(c),
+    }
+}
+
+// 转换管道中使用的 base64 编解码，和 process_encode / process_decode 的行为一致
+pub fn base64_stage(format: Base64Format, decode: bool) -> Result<Box<dyn StreamCodec>> {
+    if decode {
+        return Ok(Box::new(Blocked::new(Base64Decoder { format })));
+    }
+    if let Base64Format::Auto = format {
+        anyhow::bail!("Base64 format auto is only supported when decoding");
+    }
+    Ok(Box::new(Blocked::new(Base64Encoder { format, column: 0 })))
+}
+
+struct Base64Encoder {
+    format: Base64Format,
+    column: usize,
+}
+
+impl Base64Encoder {
+    fn wrap(&mut self, encoded: &[u8], out: &mut Vec<u8>) {
+        let Some((width, newline)) = line_wrap(self.format) else {
+            out.extend_from_slice(encoded);
+            return;
+        };
+        for &c in encoded {
+            if self.column == width {
+                out.extend_from_slice(newline);
+                self.column = 0;
+            }
+            out.push(c);
+            self.column += 1;
+        }
+    }
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> Result<Vec<u8>> {
-    let buffer = read_data(input)?;
+impl BlockCodec for Base64Encoder {
+    fn block_size(&self) -> usize {
+        STAGE_BLOCK
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let encoded = engine(self.format).encode(block);
+        self.wrap(encoded.as_bytes(), out);
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.block(tail, out)?;
+        if let Some((_, newline)) = line_wrap(self.format) {
+            if self.column > 0 {
+                out.extend_from_slice(newline);
+            }
+        }
+        Ok(())
+    }
+}
 
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buffer)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buffer)?,
+struct Base64Decoder {
+    format: Base64Format,
+}
+
+impl BlockCodec for Base64Decoder {
+    fn block_size(&self) -> usize {
+        STAGE_BLOCK
+    }
+
+    fn filter(&self, c: u8) -> Option<u8> {
+        sanitize(c, matches!(self.format, Base64Format::Auto))
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        out.extend(engine(self.format).decode(block)?);
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.block(tail, out)
+    }
+}
+
+// 每写满 width 个字符插入一次换行，结束时补上最后一行的换行
+struct LineWrap<W: Write> {
+    inner: W,
+    width: usize,
+    newline: &'static [u8],
+    column: usize,
+}
+
+impl<W: Write> LineWrap<W> {
+    fn new(inner: W, width: usize, newline: &'static [u8]) -> Self {
+        Self {
+            inner,
+            width,
+            newline,
+            column: 0,
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.column > 0 {
+            self.inner.write_all(self.newline)?;
+            self.column = 0;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for LineWrap<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(self.newline)?;
+                self.column = 0;
+            }
+            let len = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..len])?;
+            self.column += len;
+            rest = &rest[len..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// 生成 data:<mime>;base64,<data>，没有指定 MIME 类型时根据文件扩展名推断
+pub fn process_datauri_encode(input: &str, output: &str, mime: Option<&str>) -> Result<String> {
+    let mime = match mime {
+        Some(mime) => mime.to_string(),
+        None => mime_guess::from_path(input)
+            .first_or_octet_stream()
+            .to_string(),
     };
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    write!(writer, "data:{};base64,", mime)?;
+
+    let mut encoder = EncoderWriter::new(&mut writer, &STANDARD);
+    io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?;
+    drop(encoder);
+    writer.flush()?;
 
-    Ok(decoded)
+    Ok(mime)
+}
+
+// 解析 data URI 并写出原始字节，output 没有扩展名时根据 MIME 类型补上，返回实际写出的路径
+pub fn process_datauri_decode(input: &str, output: &str) -> Result<String> {
+    let mut uri = String::new();
+    get_reader(input)?.read_to_string(&mut uri)?;
+    let (mime, data) = parse_datauri(&uri)?;
+
+    let output = if output != "-" && Path::new(output).extension().is_none() {
+        match mime_extension(&mime) {
+            Some(ext) => format!("{}.{}", output, ext),
+            None => output.to_string(),
+        }
+    } else {
+        output.to_string()
+    };
+    let mut writer = get_writer(&output)?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+
+    Ok(output)
+}
+
+// data:[<mediatype>][;base64],<data>，见 RFC 2397
+fn parse_datauri(uri: &str) -> Result<(String, Vec<u8>)> {
+    let uri = uri.trim();
+    let rest = match uri.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("data:") => &uri[5..],
+        _ => anyhow::bail!("Not a data URI"),
+    };
+    let Some((meta, data)) = rest.split_once(',') else {
+        anyhow::bail!("Data URI is missing the ',' separator");
+    };
+
+    let mut params = meta.split(';').map(str::trim).collect::<Vec<_>>();
+    let base64 = params
+        .last()
+        .is_some_and(|p| p.eq_ignore_ascii_case("base64"));
+    if base64 {
+        params.pop();
+    }
+    // 省略媒体类型时默认是 text/plain;charset=US-ASCII
+    let mime = match params.first() {
+        Some(mime) if !mime.is_empty() => params.join(";"),
+        _ => "text/plain;charset=US-ASCII".to_string(),
+    };
+
+    // 数据部分可能经过百分号编码，base64 数据中还可能带有换行
+    let data = percent_decode_str(data).collect::<Vec<_>>();
+    let data = if base64 {
+        let data = data
+            .into_iter()
+            .filter_map(|c| sanitize(c, true))
+            .collect::<Vec<_>>();
+        LENIENT.decode(data)?
+    } else {
+        data
+    };
+    Ok((mime, data))
+}
+
+fn mime_extension(mime: &str) -> Option<&'static str> {
+    let essence = mime.split(';').next()?.trim().to_lowercase();
+    // mime_guess 按扩展名字母顺序返回，常见类型指定更通用的扩展名
+    let ext = match essence.as_str() {
+        "image/jpeg" => "jpg",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "text/javascript" | "application/javascript" => "js",
+        "application/octet-stream" => "bin",
+        _ => mime_guess::get_mime_extensions_str(&essence)?.first()?,
+    };
+    Some(ext)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{process_decode, process_encode, Base64Format};
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_process_encode() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("Cargo.toml.b64");
+        let output = output.to_str().unwrap();
+
+        let size = process_encode("Cargo.toml", output, Base64Format::Standard)?;
+        assert_eq!(size, fs::metadata("Cargo.toml")?.len());
+        assert_eq!(
+            base64::Engine::decode(&STANDARD, fs::read(output)?)?,
+            fs::read("Cargo.toml")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_decode() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("decoded");
+        let output = output.to_str().unwrap();
+        process_decode("fixtures/b64_encode.txt", output, Base64Format::UrlSafe)?;
+        assert!(!fs::read(output)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+        // 所有字节值，并且超过一个 8 KiB 的复制缓冲区
+        let data = (0..=255u8).cycle().take(20_000).collect::<Vec<_>>();
+        fs::write(path("data.bin"), &data)?;
+
+        for format in [Base64Format::Standard, Base64Format::UrlSafe] {
+            process_encode(&path("data.bin"), &path("data.b64"), format)?;
+            // 模拟按行折叠的编码
+            let encoded = fs::read_to_string(path("data.b64"))?;
+            let wrapped = encoded
+                .as_bytes()
+                .chunks(76)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n");
+            fs::write(path("data.b64"), wrapped + "\n")?;
+
+            let size = process_decode(&path("data.b64"), &path("data.out"), format)?;
+            assert_eq!(size, data.len() as u64);
+            assert_eq!(fs::read(path("data.out"))?, data);
+        }
+        Ok(())
+    }
 
     #[test]
-    fn test_process_encode() {
-        let input = "Cargo.toml";
-        let format = Base64Format::Standard;
-        assert!(process_encode(input, format).is_ok());
+    fn test_variants_and_lenient_decode() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        // 长度不是 3 的倍数，并且包含会编码出 + / 的字节
+        let data = (0..=255u8).rev().cycle().take(1000).collect::<Vec<_>>();
+        fs::write(path("data.bin"), &data)?;
+
+        for format in [
+            Base64Format::Standard,
+            Base64Format::StandardNoPad,
+            Base64Format::UrlSafe,
+            Base64Format::UrlSafePad,
+            Base64Format::Mime,
+            Base64Format::Pem,
+        ] {
+            process_encode(&path("data.bin"), &path("data.b64"), format)?;
+            let encoded = fs::read_to_string(path("data.b64"))?;
+
+            match format {
+                Base64Format::Mime => {
+                    assert!(encoded.ends_with("\r\n"));
+                    assert!(encoded.split("\r\n").all(|line| line.len() <= 76));
+                    assert_eq!(encoded.split("\r\n").next().unwrap().len(), 76);
+                }
+                Base64Format::Pem => {
+                    assert!(!encoded.contains('\r'));
+                    assert!(encoded.lines().all(|line| line.len() <= 64));
+                    assert_eq!(encoded.lines().next().unwrap().len(), 64);
+                }
+                _ => assert!(!encoded.contains('\n')),
+            }
+
+            for decode_format in [format, Base64Format::Auto] {
+                process_decode(&path("data.b64"), &path("data.out"), decode_format)?;
+                assert_eq!(fs::read(path("data.out"))?, data);
+            }
+        }
+
+        assert!(process_encode(&path("data.bin"), &path("data.b64"), Base64Format::Auto).is_err());
+        Ok(())
     }
 
     #[test]
-    fn test_process_decode() {
-        let input = "fixtures/b64_encode.txt";
-        let format = Base64Format::UrlSafe;
-        assert!(process_decode(input, format).is_ok())
+    fn test_datauri() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let uri = dir.path().join("logo.uri");
+        let uri = uri.to_str().unwrap();
+
+        let mime = process_datauri_encode("fixtures/index.html", uri, None)?;
+        assert_eq!(mime, "text/html");
+        assert!(fs::read_to_string(uri)?.starts_with("data:text/html;base64,"));
+
+        let output = dir.path().join("index");
+        let output = process_datauri_decode(uri, output.to_str().unwrap())?;
+        assert!(output.ends_with("index.html"));
+        assert_eq!(fs::read(output)?, fs::read("fixtures/index.html")?);
+
+        assert_eq!(
+            parse_datauri("data:,Hello%2C%20World%21")?,
+            (
+                "text/plain;charset=US-ASCII".to_string(),
+                b"Hello, World!".to_vec()
+            )
+        );
+        assert_eq!(
+            parse_datauri("DATA:image/png;BASE64,iVBO\nRw==")?.0,
+            "image/png"
+        );
+        assert_eq!(mime_extension("image/jpeg"), Some("jpg"));
+        assert_eq!(mime_extension("image/png"), Some("png"));
+        assert!(parse_datauri("http://example.com").is_err());
+        Ok(())
     }
 }
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use super::{
+    b64::{process_decode, process_encode},
+    hash::process_hash,
+};
+use crate::{Base64Format, HashAlgorithm};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+const ENCODED_EXT: &str = "b64";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    // 例如 standard、urlsafe，和 --format 的取值一致
+    pub format: String,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    // 相对于源目录的路径，统一使用 / 分隔
+    pub path: String,
+    pub encoded: String,
+    pub size: u64,
+    pub blake3: String,
+}
+
+// 编码目录下所有匹配的文件，保持目录结构，每个文件写出 <原文件名>.b64，
+// 并在输出目录写出 manifest.json；输出目录和源目录相同时即原地编码
+pub fn process_encode_dir(
+    dir: &Path,
+    out_dir: &Path,
+    format: Base64Format,
+    include: &[String],
+    exclude: &[String],
+) -> Result<Manifest> {
+    let include = build_globset(include)?;
+    let exclude = build_globset(exclude)?;
+
+    fs::create_dir_all(out_dir)?;
+    let out_dir_real = fs::canonicalize(out_dir)?;
+    let in_place = fs::canonicalize(dir)? == out_dir_real;
+
+    let mut files = Vec::new();
+    // 输出目录在源目录中时不遍历它，避免再次运行时把上次的输出也编码进去
+    let walker = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            in_place
+                || !entry.file_type().is_dir()
+                || fs::canonicalize(entry.path()).ok().as_deref() != Some(out_dir_real.as_path())
+        });
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(dir)?;
+        let path = rel_path(rel);
+        // 原地编码时跳过之前生成的文件
+        if in_place && (path == MANIFEST_NAME || path.ends_with(&format!(".{}", ENCODED_EXT))) {
+            continue;
+        }
+        if !include.as_ref().is_none_or(|set| set.is_match(rel))
+            || exclude.as_ref().is_some_and(|set| set.is_match(rel))
+        {
+            continue;
+        }
+
+        let encoded = format!("{}.{}", path, ENCODED_EXT);
+        let target = out_dir.join(&encoded);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let size = process_encode(path_str(entry.path())?, path_str(&target)?, format)?;
+        files.push(ManifestEntry {
+            path,
+            encoded,
+            size,
+            blake3: hash_file(entry.path())?,
+        });
+    }
+
+    let manifest = Manifest {
+        format: format.to_string(),
+        files,
+    };
+    let writer = File::create(out_dir.join(MANIFEST_NAME))?;
+    serde_json::to_writer_pretty(writer, &manifest)?;
+
+    Ok(manifest)
+}
+
+// 按 manifest.json 解码目录中的文件并校验大小和 blake3 哈希，
+// 返回校验失败的文件，解码出错的文件直接报错
+pub fn process_decode_dir(dir: &Path, out_dir: &Path) -> Result<Vec<String>> {
+    let manifest_path = dir.join(MANIFEST_NAME);
+    let manifest: Manifest = serde_json::from_reader(
+        File::open(&manifest_path)
+            .with_context(|| format!("Cannot open {}", manifest_path.display()))?,
+    )?;
+
+    let format: Base64Format = manifest.format.parse()?;
+    let mut mismatched = Vec::new();
+    for entry in &manifest.files {
+        let (source, target) = (
+            safe_join(dir, &entry.encoded)?,
+            safe_join(out_dir, &entry.path)?,
+        );
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let size = process_decode(path_str(&source)?, path_str(&target)?, format)
+            .with_context(|| format!("Failed to decode {}", entry.encoded))?;
+        if size != entry.size || hash_file(&target)? != entry.blake3 {
+            mismatched.push(entry.path.clone());
+        }
+    }
+
+    Ok(mismatched)
+}
+
+fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    process_hash(path_str(path)?, HashAlgorithm::Blake3)
+}
+
+pub(super) fn rel_path(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// manifest 来自外部，不允许通过绝对路径或 .. 写到输出目录之外
+fn safe_join(base: &Path, rel: &str) -> Result<PathBuf> {
+    let rel = Path::new(rel);
+    if rel
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        anyhow::bail!("Unsafe path in manifest: {}", rel.display());
+    }
+    Ok(base.join(rel))
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str()
+        .with_context(|| format!("Non UTF-8 path: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (src, encoded, restored) = (
+            dir.path().join("src"),
+            dir.path().join("encoded"),
+            dir.path().join("restored"),
+        );
+        fs::create_dir_all(src.join("img/icons"))?;
+        fs::write(src.join("index.html"), "<html></html>")?;
+        fs::write(
+            src.join("img/icons/logo.png"),
+            [0x89, b'P', b'N', b'G', 0, 255],
+        )?;
+        fs::write(src.join("notes.tmp"), "skip me")?;
+
+        let manifest = process_encode_dir(
+            &src,
+            &encoded,
+            Base64Format::Standard,
+            &[],
+            &["*.tmp".to_string()],
+        )?;
+        let paths = manifest
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["img/icons/logo.png", "index.html"]);
+        assert!(encoded.join("img/icons/logo.png.b64").exists());
+        assert_eq!(manifest.files[1].size, 13);
+
+        assert!(process_decode_dir(&encoded, &restored)?.is_empty());
+        assert_eq!(
+            fs::read(restored.join("img/icons/logo.png"))?,
+            fs::read(src.join("img/icons/logo.png"))?
+        );
+
+        // 篡改编码后的文件会被检测出来
+        fs::write(encoded.join("index.html.b64"), "PGh0bWw+")?;
+        assert_eq!(process_decode_dir(&encoded, &restored)?, ["index.html"]);
+
+        // 只编码匹配 include 的文件，原地写出
+        let manifest =
+            process_encode_dir(&src, &src, Base64Format::UrlSafe, &["**/*.png".into()], &[])?;
+        assert_eq!(manifest.files.len(), 1);
+        assert!(src.join("img/icons/logo.png.b64").exists());
+        assert!(src.join(MANIFEST_NAME).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_dir_nested_out_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path();
+        fs::write(src.join("data.b64"), "not generated by rcli")?;
+        fs::write(src.join(MANIFEST_NAME), "{}")?;
+
+        // 输出到单独的目录时，源目录中的 .b64 和 manifest.json 也要编码
+        let out = src.join("out");
+        let paths = |manifest: Manifest| {
+            manifest
+                .files
+                .into_iter()
+                .map(|f| f.path)
+                .collect::<Vec<_>>()
+        };
+        let expected = ["data.b64", MANIFEST_NAME];
+        assert_eq!(
+            paths(process_encode_dir(
+                src,
+                &out,
+                Base64Format::Standard,
+                &[],
+                &[]
+            )?),
+            expected
+        );
+        // 再次运行时不会编码上次写到 out 中的文件
+        assert_eq!(
+            paths(process_encode_dir(
+                src,
+                &out,
+                Base64Format::Standard,
+                &[],
+                &[]
+            )?),
+            expected
+        );
+        assert!(!out.join("out").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_join() {
+        assert!(safe_join(Path::new("out"), "a/b.txt").is_ok());
+        assert!(safe_join(Path::new("out"), "../etc/passwd").is_err());
+        assert!(safe_join(Path::new("out"), "/etc/passwd").is_err());
+    }
+}
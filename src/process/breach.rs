@@ -0,0 +1,136 @@
+use anyhow::Result;
+use sha1::{Digest, Sha1};
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+// 本地下载的 Pwned Passwords 数据，支持两种格式：
+// 1. 单个按哈希排序的文件，每行 "SHA1:COUNT"
+// 2. 按 k-anonymity 前缀拆分的目录，每个前缀一个文件（如 5BAA6.txt），每行 "SUFFIX:COUNT"
+#[derive(Debug)]
+pub enum BreachDb {
+    File(PathBuf),
+    Ranges(PathBuf),
+}
+
+impl BreachDb {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Ok(Self::Ranges(path.to_path_buf()))
+        } else if path.is_file() {
+            Ok(Self::File(path.to_path_buf()))
+        } else {
+            anyhow::bail!("Breach database not found: {}", path.display())
+        }
+    }
+
+    // 返回密码在泄露数据中出现的次数，没有出现时返回 0
+    pub fn lookup(&self, password: &str) -> Result<u64> {
+        let hash = sha1_hex(password);
+        match self {
+            Self::File(path) => search_sorted(path, &hash),
+            Self::Ranges(dir) => {
+                let (prefix, suffix) = hash.split_at(5);
+                let path = [format!("{}.txt", prefix), prefix.to_string()]
+                    .into_iter()
+                    .map(|name| dir.join(name))
+                    .find(|p| p.is_file());
+                match path {
+                    Some(path) => search_sorted(&path, suffix),
+                    None => Ok(0),
+                }
+            }
+        }
+    }
+}
+
+fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect()
+}
+
+// 在按哈希排序的文件中二分查找，每次只读取一行，文件再大也只占用常量内存
+fn search_sorted(path: &Path, target: &str) -> Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut lo = 0;
+    let mut hi = reader.get_ref().metadata()?.len();
+    let mut line = String::new();
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        // 找到 mid 之后（含）第一行的起始位置
+        let start = if mid == 0 {
+            reader.seek(SeekFrom::Start(0))?;
+            0
+        } else {
+            reader.seek(SeekFrom::Start(mid - 1))?;
+            line.clear();
+            mid - 1 + reader.read_line(&mut line)? as u64
+        };
+
+        line.clear();
+        let len = reader.read_line(&mut line)? as u64;
+        if len == 0 {
+            hi = mid;
+            continue;
+        }
+
+        let (key, count) = line
+            .trim_end()
+            .split_once(':')
+            .unwrap_or((line.trim_end(), "0"));
+        match compare_hex(key, target) {
+            Ordering::Equal => return Ok(count.trim().parse()?),
+            Ordering::Less => lo = start + len,
+            Ordering::Greater => hi = mid,
+        }
+    }
+
+    Ok(0)
+}
+
+fn compare_hex(a: &str, b: &str) -> Ordering {
+    a.bytes()
+        .map(|c| c.to_ascii_uppercase())
+        .cmp(b.bytes().map(|c| c.to_ascii_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breach_db_file() -> Result<()> {
+        let db = BreachDb::open("fixtures/pwned.txt")?;
+        assert_eq!(db.lookup("password")?, 9545824);
+        assert_eq!(db.lookup("hunter2")?, 17043);
+        // 第一行和最后一行
+        assert_eq!(db.lookup("juventus")?, 16345);
+        assert_eq!(db.lookup("123456789")?, 16629796);
+        assert_eq!(db.lookup("M#&]gURpz4+8)jN7B3sY")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_breach_db_ranges() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        // sha1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        std::fs::write(
+            dir.path().join("5BAA6.txt"),
+            "003D68EB55068C33ACE09247EE4C639306B:3\r\n\
+             1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n\
+             F2B5D7A9E1C0B4A3D8E6F7A1B2C3D4E5F60:1\r\n",
+        )?;
+
+        let db = BreachDb::open(dir.path())?;
+        assert_eq!(db.lookup("password")?, 9545824);
+        assert_eq!(db.lookup("hunter2")?, 0);
+        Ok(())
+    }
+}
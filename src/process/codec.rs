@@ -0,0 +1,802 @@
+use anyhow::Result;
+use data_encoding::{Encoding, Specification, BASE32, BASE32_NOPAD, HEXLOWER_PERMISSIVE};
+use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::{
+    io::{ErrorKind, Read, Write},
+    mem,
+    sync::LazyLock,
+};
+
+use crate::{get_reader, get_writer, Codec};
+
+// URI 中保留 RFC 3986 的非保留字符，其余都百分号编码
+pub(crate) const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+const HEX_LOWER: &[u8; 16] = b"0123456789abcdef";
+const Z85: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+// RFC 2045 每行最多 76 个字符，包含软换行的 =
+const QP_LINE: usize = 76;
+
+// Crockford base32 不区分大小写，并把容易混淆的 I/L 当作 1，O 当作 0
+static CROCKFORD: LazyLock<Encoding> = LazyLock::new(|| {
+    let mut spec = Specification::new();
+    spec.symbols.push_str("0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+    spec.translate.from.push_str("abcdefghjkmnpqrstvwxyziIlLoO");
+    spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ111100");
+    spec.encoding()
+        .expect("invalid Crockford base32 specification")
+});
+
+// 流式编解码器，输入可以按任意大小分块传入，跨块的状态由编解码器自己保存
+pub trait StreamCodec {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()>;
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()>;
+}
+
+// 按固定大小的块处理的编码，例如 base32 每 5 个字节编码为 8 个字符
+pub(crate) trait BlockCodec {
+    fn block_size(&self) -> usize;
+
+    // 返回 None 表示跳过该字符，例如空白字符和分隔符
+    fn filter(&self, c: u8) -> Option<u8> {
+        Some(c)
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()>;
+
+    // 最后不足一个块的数据，可能为空
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()>;
+}
+
+// 把 BlockCodec 适配为 StreamCodec，缓存不足一个块的数据
+pub(crate) struct Blocked<C> {
+    codec: C,
+    buf: Vec<u8>,
+}
+
+pub fn process_codec(
+    input: &str,
+    output: &str,
+    codec: Codec,
+    decode: bool,
+    separator: &str,
+) -> Result<u64> {
+    let mut codec = if decode {
+        new_decoder(codec)
+    } else {
+        new_encoder(codec, separator)
+    };
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+
+    let size = pump(&mut reader, &mut writer, codec.as_mut())?;
+    writer.flush()?;
+
+    Ok(size)
+}
+
+// 分块读取输入并写出结果，返回写出的字节数
+pub fn pump(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: &mut dyn StreamCodec,
+) -> Result<u64> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut out = Vec::new();
+    let mut size = 0;
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        out.clear();
+        codec.update(&buf[..n], &mut out)?;
+        writer.write_all(&out)?;
+        size += out.len() as u64;
+    }
+
+    out.clear();
+    codec.finish(&mut out)?;
+    writer.write_all(&out)?;
+    size += out.len() as u64;
+
+    Ok(size)
+}
+
+// separator 只用于 hex，插入在每个字节之间
+pub fn new_encoder(codec: Codec, separator: &str) -> Box<dyn StreamCodec> {
+    match codec {
+        Codec::Hex => Box::new(HexEncoder {
+            separator: separator.as_bytes().to_vec(),
+            first: true,
+        }),
+        Codec::Base32 => Box::new(Blocked::new(Base32Encoder(BASE32))),
+        Codec::Base32Crockford => Box::new(Blocked::new(Base32Encoder(CROCKFORD.clone()))),
+        Codec::Base58 => Box::new(Blocked::new(Base58Codec::new(false, false))),
+        Codec::Base58Check => Box::new(Blocked::new(Base58Codec::new(true, false))),
+        Codec::Ascii85 => Box::new(Blocked::new(Ascii85Encoder)),
+        Codec::Z85 => Box::new(Blocked::new(Z85Codec { decode: false })),
+        Codec::Percent => Box::new(PercentEncoder),
+        Codec::QuotedPrintable => Box::new(QpEncoder {
+            column: 0,
+            prev: None,
+            crlf: false,
+        }),
+    }
+}
+
+// 解码时忽略空白字符，hex 还会忽略常见的分隔符
+pub fn new_decoder(codec: Codec) -> Box<dyn StreamCodec> {
+    match codec {
+        Codec::Hex => Box::new(Blocked::new(HexDecoder)),
+        Codec::Base32 => Box::new(Blocked::new(Base32Decoder {
+            encoding: BASE32_NOPAD,
+            crockford: false,
+        })),
+        Codec::Base32Crockford => Box::new(Blocked::new(Base32Decoder {
+            encoding: CROCKFORD.clone(),
+            crockford: true,
+        })),
+        Codec::Base58 => Box::new(Blocked::new(Base58Codec::new(false, true))),
+        Codec::Base58Check => Box::new(Blocked::new(Base58Codec::new(true, true))),
+        Codec::Ascii85 => Box::new(Ascii85Decoder::default()),
+        Codec::Z85 => Box::new(Blocked::new(Z85Codec { decode: true })),
+        Codec::Percent => Box::new(PercentDecoder::default()),
+        Codec::QuotedPrintable => Box::new(QpDecoder::default()),
+    }
+}
+
+impl<C: BlockCodec> Blocked<C> {
+    pub(crate) fn new(codec: C) -> Self {
+        Self {
+            codec,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl<C: BlockCodec> StreamCodec for Blocked<C> {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.buf
+            .extend(input.iter().filter_map(|&c| self.codec.filter(c)));
+
+        let size = self.codec.block_size();
+        let whole = self.buf.len() / size * size;
+        for block in self.buf[..whole].chunks(size) {
+            self.codec.block(block, out)?;
+        }
+        self.buf.drain(..whole);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        let tail = mem::take(&mut self.buf);
+        self.codec.tail(&tail, out)
+    }
+}
+
+struct HexEncoder {
+    separator: Vec<u8>,
+    first: bool,
+}
+
+impl StreamCodec for HexEncoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for &b in input {
+            if !self.first {
+                out.extend_from_slice(&self.separator);
+            }
+            self.first = false;
+            out.push(HEX_LOWER[(b >> 4) as usize]);
+            out.push(HEX_LOWER[(b & 0x0f) as usize]);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut Vec<u8>) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct HexDecoder;
+
+impl BlockCodec for HexDecoder {
+    fn block_size(&self) -> usize {
+        2
+    }
+
+    fn filter(&self, c: u8) -> Option<u8> {
+        (!c.is_ascii_whitespace() && !b":-,".contains(&c)).then_some(c)
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        out.extend(HEXLOWER_PERMISSIVE.decode(block)?);
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+        if !tail.is_empty() {
+            anyhow::bail!("Hex input has an odd number of digits");
+        }
+        Ok(())
+    }
+}
+
+struct Base32Encoder(Encoding);
+
+impl BlockCodec for Base32Encoder {
+    fn block_size(&self) -> usize {
+        5
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        out.extend(self.0.encode(block).into_bytes());
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.block(tail, out)
+    }
+}
+
+// 填充字符可有可无，RFC 4648 字符表同时接受小写
+struct Base32Decoder {
+    encoding: Encoding,
+    crockford: bool,
+}
+
+impl BlockCodec for Base32Decoder {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn filter(&self, c: u8) -> Option<u8> {
+        match c {
+            c if c.is_ascii_whitespace() => None,
+            // Crockford 允许用 - 分组
+            b'-' if self.crockford => None,
+            c if self.crockford => Some(c),
+            c => Some(c.to_ascii_uppercase()),
+        }
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let len = block.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+        out.extend(self.encoding.decode(&block[..len])?);
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.block(tail, out)
+    }
+}
+
+// base58 是整体的大数进制转换，无法分块处理，只适合密钥、地址等较短的数据
+struct Base58Codec {
+    check: bool,
+    decode: bool,
+}
+
+impl Base58Codec {
+    fn new(check: bool, decode: bool) -> Self {
+        Self { check, decode }
+    }
+}
+
+impl BlockCodec for Base58Codec {
+    fn block_size(&self) -> usize {
+        usize::MAX
+    }
+
+    fn filter(&self, c: u8) -> Option<u8> {
+        (!self.decode || !c.is_ascii_whitespace()).then_some(c)
+    }
+
+    fn block(&mut self, _block: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+        unreachable!("base58 is processed as a whole")
+    }
+
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let data = match (self.decode, self.check) {
+            (false, false) => bs58::encode(tail).into_vec(),
+            (false, true) => bs58::encode(tail).with_check().into_vec(),
+            (true, false) => bs58::decode(tail).into_vec()?,
+            (true, true) => bs58::decode(tail).with_check(None).into_vec()?,
+        };
+        out.extend(data);
+        Ok(())
+    }
+}
+
+// 4 个字节按大端转换为 5 个 85 进制数字
+fn encode85(block: &[u8], alphabet: impl Fn(u32) -> u8) -> [u8; 5] {
+    let mut value = u32::from_be_bytes(block.try_into().expect("block must be 4 bytes"));
+    let mut chars = [0u8; 5];
+    for c in chars.iter_mut().rev() {
+        *c = alphabet(value % 85);
+        value /= 85;
+    }
+    chars
+}
+
+fn decode85(digits: &[u8; 5]) -> Result<[u8; 4]> {
+    let value = digits.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+    let value = u32::try_from(value).map_err(|_| anyhow::anyhow!("Base85 group overflows"))?;
+    Ok(value.to_be_bytes())
+}
+
+// btoa 风格的 ascii85，全 0 的块缩写为 z，不输出 <~ ~> 定界符
+struct Ascii85Encoder;
+
+impl BlockCodec for Ascii85Encoder {
+    fn block_size(&self) -> usize {
+        4
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if block.iter().all(|&b| b == 0) {
+            out.push(b'z');
+        } else {
+            out.extend(encode85(block, |d| d as u8 + b'!'));
+        }
+        Ok(())
+    }
+
+    // 不足 4 个字节时补 0 编码，只输出 n + 1 个字符
+    fn tail(&mut self, tail: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if !tail.is_empty() {
+            let mut block = [0u8; 4];
+            block[..tail.len()].copy_from_slice(tail);
+            out.extend(&encode85(&block, |d| d as u8 + b'!')[..tail.len() + 1]);
+        }
+        Ok(())
+    }
+}
+
+// z 会展开为 4 个字节，所以不能用 Blocked，可选的 <~ ~> 定界符会被去掉
+#[derive(Default)]
+struct Ascii85Decoder {
+    digits: Vec<u8>,
+    // 开头读到 < 时还不能确定是否是定界符
+    pending_lt: bool,
+    started: bool,
+    done: bool,
+}
+
+impl Ascii85Decoder {
+    fn push(&mut self, c: u8, out: &mut Vec<u8>) -> Result<()> {
+        match c {
+            b'z' if self.digits.is_empty() => out.extend([0u8; 4]),
+            b'!'..=b'u' => {
+                self.digits.push(c - b'!');
+                if self.digits.len() == 5 {
+                    let digits = mem::take(&mut self.digits);
+                    out.extend(decode85(&digits.try_into().expect("5 digits"))?);
+                }
+            }
+            c => anyhow::bail!("Invalid ascii85 character: {:?}", c as char),
+        }
+        Ok(())
+    }
+}
+
+impl StreamCodec for Ascii85Decoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for &c in input {
+            if self.done || c.is_ascii_whitespace() {
+                continue;
+            }
+            if !self.started {
+                self.started = true;
+                if c == b'<' {
+                    self.pending_lt = true;
+                    continue;
+                }
+            }
+            if mem::take(&mut self.pending_lt) {
+                // <~ 开始定界符
+                if c == b'~' {
+                    continue;
+                }
+                self.push(b'<', out)?;
+            }
+            // ~> 结束定界符，之后的内容都忽略
+            if c == b'~' {
+                self.done = true;
+                continue;
+            }
+            self.push(c, out)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if mem::take(&mut self.pending_lt) {
+            self.push(b'<', out)?;
+        }
+        match self.digits.len() {
+            0 => Ok(()),
+            1 => anyhow::bail!("Ascii85 input ends with a single character"),
+            n => {
+                // 用最大数字 u 补齐，解码后只保留 n - 1 个字节
+                let mut digits = [84u8; 5];
+                digits[..n].copy_from_slice(&self.digits);
+                out.extend(&decode85(&digits)?[..n - 1]);
+                self.digits.clear();
+                Ok(())
+            }
+        }
+    }
+}
+
+// ZeroMQ Z85，要求数据长度是 4 的倍数
+struct Z85Codec {
+    decode: bool,
+}
+
+impl BlockCodec for Z85Codec {
+    fn block_size(&self) -> usize {
+        if self.decode {
+            5
+        } else {
+            4
+        }
+    }
+
+    fn filter(&self, c: u8) -> Option<u8> {
+        (!self.decode || !c.is_ascii_whitespace()).then_some(c)
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if !self.decode {
+            out.extend(encode85(block, |d| Z85[d as usize]));
+            return Ok(());
+        }
+
+        let mut digits = [0u8; 5];
+        for (digit, c) in digits.iter_mut().zip(block) {
+            *digit = Z85
+                .iter()
+                .position(|z| z == c)
+                .ok_or_else(|| anyhow::anyhow!("Invalid Z85 character: {:?}", *c as char))?
+                as u8;
+        }
+        out.extend(decode85(&digits)?);
+        Ok(())
+    }
+
+    fn tail(&mut self, tail: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+        if !tail.is_empty() {
+            anyhow::bail!(
+                "Z85 {} length must be a multiple of {}",
+                if self.decode { "input" } else { "data" },
+                self.block_size()
+            );
+        }
+        Ok(())
+    }
+}
+
+// 除 RFC 3986 非保留字符外全部编码，结果可以直接用作 URL 的一部分
+struct PercentEncoder;
+
+impl StreamCodec for PercentEncoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for s in percent_encode(input, URI_COMPONENT) {
+            out.extend_from_slice(s.as_bytes());
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut Vec<u8>) -> Result<()> {
+        Ok(())
+    }
+}
+
+// 不合法的转义按原样输出，分块边界上不完整的转义留到下一块处理
+#[derive(Default)]
+struct PercentDecoder {
+    carry: Vec<u8>,
+}
+
+impl StreamCodec for PercentDecoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let mut data = mem::take(&mut self.carry);
+        data.extend_from_slice(input);
+
+        let mut i = 0;
+        while i < data.len() {
+            if data[i] == b'%' {
+                match &data[i + 1..] {
+                    [h, l, ..] if h.is_ascii_hexdigit() && l.is_ascii_hexdigit() => {
+                        out.push(hex_value(*h) << 4 | hex_value(*l));
+                        i += 3;
+                        continue;
+                    }
+                    [] | [_] => {
+                        self.carry = data[i..].to_vec();
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+            out.push(data[i]);
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        out.append(&mut self.carry);
+        Ok(())
+    }
+}
+
+// 按 RFC 2045，\r\n 作为硬换行原样输出，单独的 \r 或 \n 编码为 =0D / =0A 以保证二进制数据可以还原，
+// 超过 76 个字符时插入 =\r\n 软换行
+// 需要看到下一个字节才能决定行尾的空白字符和 \r 如何编码，所以延迟一个字节处理
+struct QpEncoder {
+    column: usize,
+    prev: Option<u8>,
+    // 上一个 \r 是硬换行的一部分
+    crlf: bool,
+}
+
+impl QpEncoder {
+    fn emit(&mut self, token: &[u8], out: &mut Vec<u8>) {
+        if self.column + token.len() > QP_LINE - 1 {
+            out.extend_from_slice(b"=\r\n");
+            self.column = 0;
+        }
+        out.extend_from_slice(token);
+        self.column += token.len();
+    }
+
+    fn encode_byte(&mut self, b: u8, next: Option<u8>, out: &mut Vec<u8>) {
+        match b {
+            b'\r' if next == Some(b'\n') => self.crlf = true,
+            b'\n' if self.crlf => {
+                out.extend_from_slice(b"\r\n");
+                self.column = 0;
+                self.crlf = false;
+            }
+            // 行尾的空白字符可能被传输过程去掉，必须编码
+            b' ' | b'\t' if !matches!(next, None | Some(b'\r' | b'\n')) => self.emit(&[b], out),
+            33..=126 if b != b'=' => self.emit(&[b], out),
+            _ => self.emit(
+                &[
+                    b'=',
+                    HEX_UPPER[(b >> 4) as usize],
+                    HEX_UPPER[(b & 0x0f) as usize],
+                ],
+                out,
+            ),
+        }
+    }
+}
+
+impl StreamCodec for QpEncoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for &b in input {
+            if let Some(prev) = self.prev.replace(b) {
+                self.encode_byte(prev, Some(b), out);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if let Some(prev) = self.prev.take() {
+            self.encode_byte(prev, None, out);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct QpDecoder {
+    carry: Vec<u8>,
+}
+
+impl StreamCodec for QpDecoder {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let mut data = mem::take(&mut self.carry);
+        data.extend_from_slice(input);
+
+        let mut i = 0;
+        while i < data.len() {
+            if data[i] == b'=' {
+                match &data[i + 1..] {
+                    // 软换行
+                    [b'\r', b'\n', ..] => {
+                        i += 3;
+                        continue;
+                    }
+                    [b'\n', ..] => {
+                        i += 2;
+                        continue;
+                    }
+                    [h, l, ..] if h.is_ascii_hexdigit() && l.is_ascii_hexdigit() => {
+                        out.push(hex_value(*h) << 4 | hex_value(*l));
+                        i += 3;
+                        continue;
+                    }
+                    [] | [_] => {
+                        self.carry = data[i..].to_vec();
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+            out.push(data[i]);
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        out.append(&mut self.carry);
+        Ok(())
+    }
+}
+
+fn hex_value(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODECS: [Codec; 9] = [
+        Codec::Hex,
+        Codec::Base32,
+        Codec::Base32Crockford,
+        Codec::Base58,
+        Codec::Base58Check,
+        Codec::Ascii85,
+        Codec::Z85,
+        Codec::Percent,
+        Codec::QuotedPrintable,
+    ];
+
+    // 按 step 大小分块传入，模拟流式读取
+    fn run(codec: &mut dyn StreamCodec, input: &[u8], step: usize) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for chunk in input.chunks(step) {
+            codec.update(chunk, &mut out)?;
+        }
+        codec.finish(&mut out)?;
+        Ok(out)
+    }
+
+    fn encode(codec: Codec, input: &[u8]) -> String {
+        String::from_utf8(run(new_encoder(codec, ":").as_mut(), input, 3).unwrap()).unwrap()
+    }
+
+    fn decode(codec: Codec, input: &str) -> Result<Vec<u8>> {
+        run(new_decoder(codec).as_mut(), input.as_bytes(), 3)
+    }
+
+    #[test]
+    fn test_codec_roundtrip() -> Result<()> {
+        // Z85 要求长度是 4 的倍数
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        for codec in CODECS {
+            for step in [1, 7, 4096] {
+                let encoded = run(new_encoder(codec, " ").as_mut(), &data, step)?;
+                assert!(encoded.is_ascii(), "{:?}", codec);
+                let decoded = run(new_decoder(codec).as_mut(), &encoded, step)?;
+                assert_eq!(decoded, data, "{:?} step {}", codec, step);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_vectors() -> Result<()> {
+        assert_eq!(encode(Codec::Hex, b"rcli"), "72:63:6c:69");
+        assert_eq!(decode(Codec::Hex, "72 63:6C-69\n")?, b"rcli");
+
+        assert_eq!(encode(Codec::Base32, b"foobar"), "MZXW6YTBOI======");
+        assert_eq!(decode(Codec::Base32, "mzxw6ytboi")?, b"foobar");
+        assert_eq!(encode(Codec::Base32Crockford, b"foobar"), "CSQPYRK1E8");
+        assert_eq!(decode(Codec::Base32Crockford, "csqp-yrkle8")?, b"foobar");
+
+        assert_eq!(encode(Codec::Base58, b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        let checked = encode(Codec::Base58Check, b"rcli");
+        assert_eq!(decode(Codec::Base58Check, &checked)?, b"rcli");
+        let tampered = format!(
+            "{}{}",
+            &checked[..checked.len() - 1],
+            if checked.ends_with('2') { '3' } else { '2' }
+        );
+        assert!(decode(Codec::Base58Check, &tampered).is_err());
+
+        assert_eq!(
+            encode(Codec::Ascii85, b"Man is distinguished"),
+            "9jqo^BlbD-BleB1DJ+*+F(f,q"
+        );
+        assert_eq!(encode(Codec::Ascii85, &[0, 0, 0, 0, 1]), "z!<");
+        assert_eq!(
+            decode(Codec::Ascii85, "<~9jqo^BlbD-BleB1DJ+*+F(f,q~>\n")?,
+            b"Man is distinguished"
+        );
+
+        // ZeroMQ RFC 32 中的示例
+        let hello = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+        assert_eq!(encode(Codec::Z85, &hello), "HelloWorld");
+        assert_eq!(decode(Codec::Z85, "HelloWorld")?, hello);
+        assert!(run(new_encoder(Codec::Z85, "").as_mut(), b"abc", 1).is_err());
+
+        assert_eq!(
+            encode(Codec::Percent, "a b&c/é-_.~".as_bytes()),
+            "a%20b%26c%2F%C3%A9-_.~"
+        );
+        assert_eq!(decode(Codec::Percent, "100%25%zz%4")?, b"100%%zz%4");
+
+        assert_eq!(
+            encode(Codec::QuotedPrintable, "héllo=world \nok\t".as_bytes()),
+            "h=C3=A9llo=3Dworld=20=0Aok=09"
+        );
+        assert_eq!(
+            encode(Codec::QuotedPrintable, b"a \r\nb\rc"),
+            "a=20\r\nb=0Dc"
+        );
+        assert_eq!(
+            decode(Codec::QuotedPrintable, "h=C3=A9llo=\r\n world=3")?,
+            "héllo world=3".as_bytes()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoted_printable_line_length() {
+        let encoded = encode(Codec::QuotedPrintable, &[b'='; 100]);
+        assert!(encoded.split("\r\n").all(|line| line.len() <= QP_LINE));
+        assert!(encoded.contains("=\r\n"));
+    }
+
+    #[test]
+    fn test_quoted_printable_newlines_roundtrip() -> Result<()> {
+        let text = format!(
+            "Subject: rcli\r\n\r\n{} \r\nsecond line\nbare lf\r\n",
+            "x".repeat(100)
+        );
+        for step in [1, 2, 4096] {
+            let encoded = run(
+                new_encoder(Codec::QuotedPrintable, "").as_mut(),
+                text.as_bytes(),
+                step,
+            )?;
+            // 硬换行和软换行都必须是 CRLF
+            assert!(encoded
+                .iter()
+                .enumerate()
+                .all(|(i, &b)| b != b'\n' || (i > 0 && encoded[i - 1] == b'\r')));
+            assert!(encoded
+                .split(|&b| b == b'\n')
+                .all(|line| line.len() <= QP_LINE + 1));
+            assert_eq!(
+                run(new_decoder(Codec::QuotedPrintable).as_mut(), &encoded, step)?,
+                text.as_bytes()
+            );
+        }
+        Ok(())
+    }
+}
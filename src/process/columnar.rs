@@ -0,0 +1,179 @@
+use anyhow::Result;
+use arrow::{
+    csv::{reader::Format, ReaderBuilder, WriterBuilder},
+    ipc::{reader::FileReader, writer::FileWriter},
+    json::ArrayWriter,
+    record_batch::RecordBatchReader,
+};
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    basic::{Compression, GzipLevel, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use serde_json::Value;
+use std::{fs::File, io::Seek, path::Path, sync::Arc};
+
+use crate::{CsvConvertOptions, OutputFormat, ParquetCompression};
+
+// 根据扩展名判断输入是否为 Parquet / Arrow IPC 文件
+pub fn is_columnar_input(input: &str) -> bool {
+    matches!(
+        Path::new(input).extension().and_then(|ext| ext.to_str()),
+        Some("parquet" | "arrow" | "ipc")
+    )
+}
+
+// 通过 arrow 读取输入并写出目标格式，CSV 输入的列类型由全量数据推断
+pub fn process_columnar(input: &str, output: &str, opts: &CsvConvertOptions) -> Result<()> {
+    let reader = open_batches(input, opts)?;
+    let schema = reader.schema();
+    let file = File::create(output)?;
+
+    match opts.format {
+        OutputFormat::Parquet => {
+            let props = WriterProperties::builder()
+                .set_max_row_group_size(opts.row_group_size)
+                .set_compression(opts.compression.into())
+                .build();
+            let mut writer = ArrowWriter::try_new(file, schema, Some(props))?;
+            for batch in reader {
+                writer.write(&batch?)?;
+            }
+            writer.close()?;
+        }
+        OutputFormat::Arrow => {
+            let mut writer = FileWriter::try_new(file, &schema)?;
+            for batch in reader {
+                writer.write(&batch?)?;
+            }
+            writer.finish()?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = WriterBuilder::new()
+                .with_header(true)
+                .with_delimiter(opts.delimiter as u8)
+                .build(file);
+            for batch in reader {
+                writer.write(&batch?)?;
+            }
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            // ArrayWriter 输出的是 JSON 数组，再转成 Value 以便统一美化或转换成 YAML
+            let mut writer = ArrayWriter::new(Vec::new());
+            for batch in reader {
+                writer.write(&batch?)?;
+            }
+            writer.finish()?;
+            let buf = writer.into_inner();
+            let value: Value = if buf.is_empty() {
+                Value::Array(vec![])
+            } else {
+                serde_json::from_slice(&buf)?
+            };
+            let content = match opts.format {
+                OutputFormat::Json => serde_json::to_string_pretty(&value)?,
+                _ => serde_yaml::to_string(&value)?,
+            };
+            std::fs::write(output, content)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn open_batches(input: &str, opts: &CsvConvertOptions) -> Result<Box<dyn RecordBatchReader>> {
+    let mut file = File::open(input)?;
+    let reader: Box<dyn RecordBatchReader> =
+        match Path::new(input).extension().and_then(|ext| ext.to_str()) {
+            Some("parquet") => Box::new(ParquetRecordBatchReaderBuilder::try_new(file)?.build()?),
+            Some("arrow" | "ipc") => Box::new(FileReader::try_new(file, None)?),
+            _ => {
+                let delimiter = opts.delimiter as u8;
+                let format = Format::default()
+                    .with_header(opts.header)
+                    .with_delimiter(delimiter);
+                let (schema, _) = format.infer_schema(&mut file, None)?;
+                file.rewind()?;
+                Box::new(
+                    ReaderBuilder::new(Arc::new(schema))
+                        .with_format(format)
+                        .build(file)?,
+                )
+            }
+        };
+
+    Ok(reader)
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(format: OutputFormat) -> CsvConvertOptions {
+        CsvConvertOptions {
+            format,
+            delimiter: ',',
+            header: true,
+            row_group_size: 10,
+            compression: ParquetCompression::Zstd,
+        }
+    }
+
+    #[test]
+    fn test_csv_parquet_json_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let parquet = dir.path().join("juventus.parquet");
+        let json = dir.path().join("juventus.json");
+        let parquet = parquet.to_str().unwrap();
+
+        process_columnar(
+            "assets/juventus.csv",
+            parquet,
+            &options(OutputFormat::Parquet),
+        )?;
+        let metadata = ParquetRecordBatchReaderBuilder::try_new(File::open(parquet)?)?
+            .metadata()
+            .clone();
+        assert_eq!(metadata.file_metadata().num_rows(), 27);
+        assert_eq!(metadata.num_row_groups(), 3);
+
+        process_columnar(
+            parquet,
+            json.to_str().unwrap(),
+            &options(OutputFormat::Json),
+        )?;
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(json)?)?;
+        // 推断出的列类型会保留下来，Kit Number 是整数而不是字符串
+        assert_eq!(value[0]["Kit Number"], Value::from(1));
+        assert_eq!(value[0]["DOB"], Value::from("Apr 18, 1990 (29)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_arrow_csv_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let arrow = dir.path().join("juventus.arrow");
+        let csv = dir.path().join("juventus.csv");
+        let arrow = arrow.to_str().unwrap();
+
+        process_columnar("assets/juventus.csv", arrow, &options(OutputFormat::Arrow))?;
+        process_columnar(arrow, csv.to_str().unwrap(), &options(OutputFormat::Csv))?;
+
+        assert_eq!(
+            std::fs::read_to_string(csv)?,
+            std::fs::read_to_string("assets/juventus.csv")?
+        );
+        Ok(())
+    }
+}
@@ -1,11 +1,22 @@
 use anyhow::Result;
-use csv::Reader;
+use csv::ReaderBuilder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
 
-use crate::cli::OutputFormat;
+use super::columnar::{is_columnar_input, process_columnar};
+use crate::cli::{OutputFormat, ParquetCompression};
 
+#[derive(Debug, Clone)]
+pub struct CsvConvertOptions {
+    pub format: OutputFormat,
+    pub delimiter: char,
+    pub header: bool,
+    pub row_group_size: usize,
+    pub compression: ParquetCompression,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 // 这里的字段名要和csv文件的header一致
 #[serde(rename_all = "PascalCase")]
@@ -20,10 +31,24 @@ pub struct Player {
     kit: u8,
 }
 
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
+pub fn process_csv(input: &str, output: String, opts: &CsvConvertOptions) -> Result<()> {
+    // Parquet / Arrow 需要列类型信息，CSV 输出时也复用 arrow 的 writer
+    if is_columnar_input(input)
+        || matches!(
+            opts.format,
+            OutputFormat::Csv | OutputFormat::Parquet | OutputFormat::Arrow
+        )
+    {
+        return process_columnar(input, &output, opts);
+    }
+
     // unwrap 和 ? 都是用来处理Result的，如果是Ok，unwrap会返回Ok中的值，如果是Err，unwrap会panic
     // 如果是Ok，?会返回Ok中的值，如果是Err，?会将错误传播到调用该函数的地方，而不是立即崩溃。
-    let mut reader = Reader::from_path(input)?;
+    // 和 Parquet / Arrow 路径一样使用 --delimiter 和 --header 的设置
+    let mut reader = ReaderBuilder::new()
+        .delimiter(opts.delimiter as u8)
+        .has_headers(opts.header)
+        .from_path(input)?;
     let mut ret = Vec::with_capacity(128);
     // let records = reader
     //     .deserialize::<Player>()
@@ -33,7 +58,14 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<
     // clone() 的用处
     // reader.headers() 和 reader.records() 都会声明一个 &mut self 的引用，这两个方法不能同时调用，否则会报错。
     // 通过 clone() 方法，可以将 reader.headers() 的引用克隆一份，这样就不会出现同时调用两个方法的情况。
-    let headers = reader.headers()?.clone();
+    // 没有 header 时和 arrow 一样把列命名为 column_1、column_2 ...
+    let headers = if opts.header {
+        reader.headers()?.clone()
+    } else {
+        (1..=reader.headers()?.len())
+            .map(|i| format!("column_{}", i))
+            .collect()
+    };
 
     // 读取csv文件的内容
     for result in reader.records() {
@@ -43,14 +75,13 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<
         // zip 将两个迭代器合并为一个元组的迭代器 [(headers, record), ...]
         // collect::<Value>() 将元组的迭代器转换为Value类型
         let json_value = headers.iter().zip(record.iter()).collect::<Value>();
-        println!("{:?}", json_value);
         // 将读取到的内容存入Vec<Player>中
         ret.push(json_value);
     }
 
-    let content = match format {
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
+    let content = match opts.format {
         OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
+        _ => serde_json::to_string_pretty(&ret)?,
     };
     // 通过serde_json将Vec<Player>序列化为json字符串
     // let json = serde_json::to_string_pretty(&ret)?;
@@ -59,3 +90,43 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_csv_delimiter_and_header() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (input, output) = (dir.path().join("in.csv"), dir.path().join("out.json"));
+        fs::write(&input, "Buffon;Goalkeeper\nChiellini;Defender\n")?;
+        let mut opts = CsvConvertOptions {
+            format: OutputFormat::Json,
+            delimiter: ';',
+            header: false,
+            row_group_size: 10,
+            compression: ParquetCompression::Zstd,
+        };
+
+        process_csv(
+            input.to_str().unwrap(),
+            output.to_str().unwrap().to_string(),
+            &opts,
+        )?;
+        let rows: Vec<Value> = serde_json::from_str(&fs::read_to_string(&output)?)?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["column_1"], "Buffon");
+        assert_eq!(rows[1]["column_2"], "Defender");
+
+        opts.header = true;
+        process_csv(
+            input.to_str().unwrap(),
+            output.to_str().unwrap().to_string(),
+            &opts,
+        )?;
+        let rows: Vec<Value> = serde_json::from_str(&fs::read_to_string(&output)?)?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["Buffon"], "Chiellini");
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use anyhow::Result;
+use csv::{Reader, ReaderBuilder, StringRecord, WriterBuilder};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::get_reader;
+
+// 一次归并最多同时打开的 run 文件数，超过时分多轮归并，避免超出文件描述符上限
+const MAX_FAN_IN: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct CsvSortOptions {
+    pub by: Vec<String>,
+    pub numeric: bool,
+    pub desc: bool,
+    pub delimiter: char,
+    pub chunk_size: usize,
+}
+
+// 排序用的键，numeric 模式下无法解析为数字的值按 NaN 处理，升序时排在最后
+#[derive(Debug, Clone)]
+enum SortKey {
+    Number(f64),
+    Text(String),
+}
+
+// 归并时堆中的元素，run 用于相同键时保持原有顺序（稳定排序）
+struct MergeEntry {
+    key: Vec<SortKey>,
+    record: StringRecord,
+    run: usize,
+    desc: bool,
+}
+
+pub fn process_csv_sort(input: &str, output: Option<&str>, opts: &CsvSortOptions) -> Result<()> {
+    let delimiter = delimiter_byte(opts.delimiter)?;
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(get_reader(input)?);
+    let headers = reader.headers()?.clone();
+    let columns = resolve_columns(&headers, &opts.by)?;
+
+    // 第一阶段：分块读取，每块在内存中排序后写入临时目录，生成若干有序的 run
+    let tmp = tempfile::tempdir()?;
+    let mut runs = Vec::new();
+    let mut chunk = Vec::with_capacity(opts.chunk_size.min(1024));
+    for result in reader.records() {
+        chunk.push(result?);
+        if chunk.len() >= opts.chunk_size {
+            runs.push(write_run(
+                tmp.path(),
+                runs.len(),
+                &mut chunk,
+                &columns,
+                delimiter,
+                opts,
+            )?);
+        }
+    }
+    if !chunk.is_empty() {
+        runs.push(write_run(
+            tmp.path(),
+            runs.len(),
+            &mut chunk,
+            &columns,
+            delimiter,
+            opts,
+        )?);
+    }
+
+    // 第二阶段：run 太多时先分组归并成较少的中间 run，每组最多 MAX_FAN_IN 个
+    // 按顺序分组，组内和组间的先后顺序不变，因此仍然是稳定排序
+    let mut pass = 0;
+    while runs.len() > MAX_FAN_IN {
+        runs = runs
+            .chunks(MAX_FAN_IN)
+            .enumerate()
+            .map(|(idx, group)| {
+                let path = tmp.path().join(format!("merge-{}-{:06}.csv", pass, idx));
+                let mut writer = WriterBuilder::new()
+                    .delimiter(delimiter)
+                    .has_headers(false)
+                    .from_writer(BufWriter::new(File::create(&path)?));
+                merge_runs(group, &mut writer, &columns, delimiter, opts)?;
+                writer.flush()?;
+                for run in group {
+                    std::fs::remove_file(run)?;
+                }
+                Ok(path)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        pass += 1;
+    }
+
+    // 最后一轮 k 路归并，输出时保留原始 header
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(BufWriter::new(writer));
+    writer.write_record(&headers)?;
+    merge_runs(&runs, &mut writer, &columns, delimiter, opts)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn merge_runs<W: Write>(
+    runs: &[PathBuf],
+    writer: &mut csv::Writer<W>,
+    columns: &[usize],
+    delimiter: u8,
+    opts: &CsvSortOptions,
+) -> Result<()> {
+    let mut readers = runs
+        .iter()
+        .map(|path| {
+            Ok(ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(false)
+                .from_reader(BufReader::new(File::open(path)?)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut heap = BinaryHeap::with_capacity(readers.len());
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if let Some(entry) = next_entry(reader, run, columns, opts)? {
+            heap.push(entry);
+        }
+    }
+
+    while let Some(entry) = heap.pop() {
+        writer.write_record(&entry.record)?;
+        if let Some(next) = next_entry(&mut readers[entry.run], entry.run, columns, opts)? {
+            heap.push(next);
+        }
+    }
+
+    Ok(())
+}
+
+fn delimiter_byte(delimiter: char) -> Result<u8> {
+    if delimiter.is_ascii() {
+        Ok(delimiter as u8)
+    } else {
+        anyhow::bail!("Delimiter must be an ASCII character: {}", delimiter)
+    }
+}
+
+fn resolve_columns(headers: &StringRecord, by: &[String]) -> Result<Vec<usize>> {
+    by.iter()
+        .map(|name| {
+            headers
+                .iter()
+                .position(|h| h == name.trim())
+                .ok_or_else(|| anyhow::anyhow!("Column not found: {}", name))
+        })
+        .collect()
+}
+
+fn sort_key(record: &StringRecord, columns: &[usize], numeric: bool) -> Vec<SortKey> {
+    columns
+        .iter()
+        .map(|&idx| {
+            let value = record.get(idx).unwrap_or_default();
+            if numeric {
+                SortKey::Number(value.trim().parse().unwrap_or(f64::NAN))
+            } else {
+                SortKey::Text(value.to_string())
+            }
+        })
+        .collect()
+}
+
+fn compare_keys(a: &[SortKey], b: &[SortKey], desc: bool) -> Ordering {
+    let ord = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a, b)| match (a, b) {
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Number(_), SortKey::Text(_)) => Ordering::Less,
+            (SortKey::Text(_), SortKey::Number(_)) => Ordering::Greater,
+        })
+        .find(|ord| ord.is_ne())
+        .unwrap_or(Ordering::Equal);
+
+    if desc {
+        ord.reverse()
+    } else {
+        ord
+    }
+}
+
+fn write_run(
+    dir: &Path,
+    idx: usize,
+    chunk: &mut Vec<StringRecord>,
+    columns: &[usize],
+    delimiter: u8,
+    opts: &CsvSortOptions,
+) -> Result<PathBuf> {
+    // 先计算好每行的键，避免排序过程中反复解析
+    let mut keyed = chunk
+        .drain(..)
+        .map(|record| (sort_key(&record, columns, opts.numeric), record))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| compare_keys(&a.0, &b.0, opts.desc));
+
+    let path = dir.join(format!("run-{:06}.csv", idx));
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(BufWriter::new(File::create(&path)?));
+    for (_, record) in keyed {
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+
+    Ok(path)
+}
+
+fn next_entry(
+    reader: &mut Reader<BufReader<File>>,
+    run: usize,
+    columns: &[usize],
+    opts: &CsvSortOptions,
+) -> Result<Option<MergeEntry>> {
+    let mut record = StringRecord::new();
+    if !reader.read_record(&mut record)? {
+        return Ok(None);
+    }
+
+    Ok(Some(MergeEntry {
+        key: sort_key(&record, columns, opts.numeric),
+        record,
+        run,
+        desc: opts.desc,
+    }))
+}
+
+// BinaryHeap 是大顶堆，这里把比较结果反转，使堆顶始终是下一个应该输出的行
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, self.desc)
+            .then(self.run.cmp(&other.run))
+            .reverse()
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort_to_string(opts: &CsvSortOptions) -> Result<String> {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("sorted.csv");
+        process_csv_sort("assets/juventus.csv", output.to_str(), opts)?;
+        Ok(std::fs::read_to_string(output)?)
+    }
+
+    #[test]
+    fn test_csv_sort_numeric_across_runs() -> Result<()> {
+        let opts = CsvSortOptions {
+            by: vec!["Kit Number".to_string()],
+            numeric: true,
+            desc: false,
+            delimiter: ',',
+            chunk_size: 3,
+        };
+        let content = sort_to_string(&opts)?;
+
+        let mut reader = Reader::from_reader(content.as_bytes());
+        assert_eq!(
+            reader.headers()?,
+            vec!["Name", "Position", "DOB", "Nationality", "Kit Number"]
+        );
+        let kits = reader
+            .records()
+            .map(|r| Ok(r?[4].parse::<u32>()?))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(kits.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(kits.len(), 27);
+        // DOB 中包含逗号，输出时需要保留引号
+        assert!(content.contains("\"Apr 18, 1990 (29)\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_sort_multi_column_desc() -> Result<()> {
+        let opts = CsvSortOptions {
+            by: vec!["Position".to_string(), "Name".to_string()],
+            numeric: false,
+            desc: true,
+            delimiter: ',',
+            chunk_size: 4,
+        };
+        let content = sort_to_string(&opts)?;
+
+        let mut reader = Reader::from_reader(content.as_bytes());
+        let keys = reader
+            .records()
+            .map(|r| {
+                let r = r?;
+                Ok((r[1].to_string(), r[0].to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        assert!(keys.windows(2).all(|w| w[0] >= w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_sort_more_runs_than_fan_in() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (input, output) = (dir.path().join("in.csv"), dir.path().join("out.csv"));
+        // chunk_size 为 1 时每行一个 run，需要三轮归并
+        let rows = MAX_FAN_IN * MAX_FAN_IN + 7;
+        let mut content = String::from("id,group\n");
+        for i in 0..rows {
+            content.push_str(&format!("{},{}\n", i, (i * 7919) % 10));
+        }
+        std::fs::write(&input, content)?;
+
+        let opts = CsvSortOptions {
+            by: vec!["group".to_string()],
+            numeric: true,
+            desc: false,
+            delimiter: ',',
+            chunk_size: 1,
+        };
+        process_csv_sort(input.to_str().unwrap(), output.to_str(), &opts)?;
+
+        let mut reader = Reader::from_path(&output)?;
+        let rows_out = reader
+            .records()
+            .map(|r| {
+                let r = r?;
+                Ok((r[1].parse::<u32>()?, r[0].parse::<usize>()?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(rows_out.len(), rows);
+        // 按 group 排序，相同 group 内保持原有顺序
+        assert!(rows_out.windows(2).all(|w| w[0] < w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_sort_unknown_column() {
+        let opts = CsvSortOptions {
+            by: vec!["Salary".to_string()],
+            numeric: false,
+            desc: false,
+            delimiter: ',',
+            chunk_size: 10,
+        };
+        assert!(sort_to_string(&opts).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{sync::mpsc, time};
+use tracing::{info, warn};
+
+use super::process_csv;
+use crate::CsvConvertOptions;
+
+// 编辑器保存时往往会连续触发多个事件，在这段时间内的事件只会触发一次转换
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub async fn process_csv_watch(input: &str, output: &str, opts: &CsvConvertOptions) -> Result<()> {
+    let path = std::fs::canonicalize(input)?;
+    // 监听所在目录而不是文件本身，很多编辑器保存时会先写临时文件再 rename，直接监听文件会丢失后续事件
+    let dir = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let _ = tx.send(res);
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    info!("Watching {} for changes", path.display());
+
+    convert(input, output, opts);
+    while let Some(res) = rx.recv().await {
+        if !is_input_changed(&res, &path) {
+            continue;
+        }
+
+        // 在 DEBOUNCE 时间内没有新事件后再执行转换
+        while let Ok(Some(_)) = time::timeout(DEBOUNCE, rx.recv()).await {}
+        convert(input, output, opts);
+    }
+
+    Ok(())
+}
+
+fn convert(input: &str, output: &str, opts: &CsvConvertOptions) {
+    // 转换失败时只打印错误，继续等待下一次修改
+    match process_csv(input, output.to_string(), opts) {
+        Ok(()) => info!("Converted {} to {}", input, output),
+        Err(e) => warn!("Convert {} failed: {:#}", input, e),
+    }
+}
+
+fn is_input_changed(res: &notify::Result<Event>, path: &Path) -> bool {
+    match res {
+        Ok(event) => {
+            matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+            ) && event.paths.iter().any(|p| p == path)
+        }
+        Err(e) => {
+            warn!("Watch error: {:?}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputFormat, ParquetCompression};
+    use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+    use std::{
+        fs,
+        io::{self, Write},
+        sync::{Arc, Mutex},
+    };
+
+    // 把日志写到内存中，方便检查 warn! 的内容
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_is_input_changed() {
+        let dir = Path::new("/data");
+        let input = dir.join("players.csv");
+        let event = |kind, path: &Path| Ok(Event::new(kind).add_path(path.to_path_buf()));
+        let modify = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+
+        assert!(is_input_changed(&event(modify, &input), &input));
+        // 编辑器先写临时文件再 rename 时会产生 Create 事件
+        assert!(is_input_changed(
+            &event(EventKind::Create(CreateKind::File), &input),
+            &input
+        ));
+        assert!(!is_input_changed(
+            &event(EventKind::Remove(RemoveKind::File), &input),
+            &input
+        ));
+        // 同目录下的其他文件，包括转换输出的文件，都不会触发转换
+        assert!(!is_input_changed(
+            &event(modify, &dir.join("players.json")),
+            &input
+        ));
+        assert!(!is_input_changed(
+            &event(modify, &dir.join("other.csv")),
+            &input
+        ));
+        assert!(!is_input_changed(
+            &Err(notify::Error::generic("watch failed")),
+            &input
+        ));
+    }
+
+    #[test]
+    fn test_convert_keeps_going_on_error() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("players.csv");
+        let output = dir.path().join("players.json");
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        let opts = CsvConvertOptions {
+            format: OutputFormat::Json,
+            delimiter: ',',
+            header: true,
+            row_group_size: 10,
+            compression: ParquetCompression::Zstd,
+        };
+
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || -> Result<()> {
+            // 字段数量不一致的行会导致转换失败，convert 只记录错误
+            fs::write(input, "Name,Position\nBuffon,Goalkeeper,1978\n")?;
+            convert(input, output, &opts);
+            assert!(!Path::new(output).exists());
+
+            // 修正之后下一次转换成功
+            fs::write(input, "Name,Position\nBuffon,Goalkeeper\n")?;
+            convert(input, output, &opts);
+            Ok(())
+        })?;
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone())?;
+        assert!(logs.contains(&format!("Convert {} failed", input)));
+        assert!(logs.contains(&format!("Converted {} to {}", input, output)));
+        assert!(fs::read_to_string(output)?.contains("Buffon"));
+        Ok(())
+    }
+}
@@ -1,48 +1,372 @@
-use rand::seq::SliceRandom;
+use rand::{rngs::OsRng, seq::SliceRandom, Rng, RngCore};
+use serde::Serialize;
+use std::io::Write;
+use zxcvbn::zxcvbn;
 
-const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const NUMBER: &[u8] = b"123456789";
-const SYMBOL: &[u8] = b"!@#$%^&*_";
+use super::{PasswordPolicy, PolicyError};
+use crate::{GenPassOutputFormat, RngSource};
 
-pub fn process_gen_pass(
-    length: u8,
-    upper: bool,
-    lower: bool,
-    number: bool,
-    symbol: bool,
-) -> anyhow::Result<String> {
-    let mut password = Vec::new();
-    let mut rng = rand::thread_rng();
-    let mut chars = Vec::new();
+// json / csv 模式下每个密码输出一条记录
+#[derive(Debug, Serialize)]
+pub struct GenPassRecord {
+    pub password: String,
+    pub score: u8,
+    pub entropy: f64,
+    pub policy: String,
+}
+
+// 策略已经校验过只包含 ASCII 字符，生成的字节序列一定是合法的 UTF-8
+pub fn process_gen_pass(policy: &PasswordPolicy, source: RngSource) -> Result<String, PolicyError> {
+    policy.validate()?;
 
-    if upper {
-        chars.extend_from_slice(UPPER);
-        // 如果开启了对应配置，就从UPPER中随机选择一个字符
-        password.push(*UPPER.choose(&mut rng).expect("UPPER is empty"));
+    let mut rng = new_rng(source);
+    if policy.pronounceable {
+        return gen_pronounceable(policy, &mut rng);
     }
-    if lower {
-        chars.extend_from_slice(LOWER);
-        password.push(*LOWER.choose(&mut rng).expect("UPPER is empty"));
+
+    let mut password = Vec::with_capacity(policy.length);
+
+    // 先满足每个类的最少字符数
+    for class in &policy.classes {
+        for _ in 0..class.min {
+            let c = pick(
+                class.chars.as_bytes(),
+                &password,
+                policy.no_repeat,
+                &mut rng,
+            )
+            .ok_or_else(|| PolicyError::ClassExhausted(class.name.clone()))?;
+            password.push(c);
+        }
     }
-    if number {
-        chars.extend_from_slice(NUMBER);
-        password.push(*NUMBER.choose(&mut rng).expect("UPPER is empty"));
+
+    // 剩余长度从所有类的字符并集中随机选择
+    let chars = policy.alphabet();
+    while password.len() < policy.length {
+        let c = pick(&chars, &password, policy.no_repeat, &mut rng)
+            .ok_or(PolicyError::NotEnoughDistinct(policy.length))?;
+        password.push(c);
     }
-    if symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(*SYMBOL.choose(&mut rng).expect("UPPER is empty"));
+
+    password.shuffle(&mut rng);
+
+    // 第一个字符不允许时，和后面随机一个允许作为开头的字符交换位置
+    if let Some(&first) = password.first() {
+        if !policy.can_lead(first) {
+            let candidates = (1..password.len())
+                .filter(|&i| policy.can_lead(password[i]))
+                .collect::<Vec<_>>();
+            let idx = candidates
+                .choose(&mut rng)
+                .ok_or(PolicyError::NoLeadingChar)?;
+            password.swap(0, *idx);
+        }
     }
 
-    for _ in 0..(length - password.len() as u8) {
-        // let idx = rng.random_range(0..chars.len());
-        let c = chars.choose(&mut rng).expect("chars is empty");
-        password.push(*c);
+    Ok(String::from_utf8(password).expect("policy only contains ASCII"))
+}
+
+// 用策略中字母类允许的辅音和元音交替组成音节，再插入数字、符号等非字母类要求的字符
+fn gen_pronounceable(policy: &PasswordPolicy, rng: &mut impl Rng) -> Result<String, PolicyError> {
+    let letters = policy.letter_chars();
+    let (consonants, vowels) = policy.syllable_letters();
+    // 优先使用小写形式，不允许小写时使用大写
+    let mut password = (0..policy.letters())
+        .map(|i| {
+            let chars = if i % 2 == 0 { &consonants } else { &vowels };
+            let c = *chars.choose(rng).expect("letters are empty");
+            if letters.contains(&c) {
+                c
+            } else {
+                c.to_ascii_uppercase()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // 大写字母不够时，从允许大写的小写字母中随机选择若干个改为大写
+    let upper = password.iter().filter(|c| c.is_ascii_uppercase()).count();
+    let candidates = password
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_ascii_lowercase() && letters.contains(&c.to_ascii_uppercase()))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let missing = policy.upper_min().saturating_sub(upper);
+    if missing > candidates.len() {
+        return Err(PolicyError::PronounceableUpper {
+            letters: password.len(),
+            upper: policy.upper_min(),
+        });
+    }
+    for idx in candidates.choose_multiple(rng, missing) {
+        password[*idx].make_ascii_uppercase();
     }
 
-    password.shuffle(&mut rng);
+    for class in policy.classes.iter().filter(|class| !class.is_letters()) {
+        for _ in 0..class.min {
+            let c = *class.chars.as_bytes().choose(rng).expect("class is empty");
+            let start = if policy.can_lead(c) { 0 } else { 1 };
+            if start > password.len() {
+                return Err(PolicyError::NoLeadingChar);
+            }
+            let idx = rng.gen_range(start..=password.len());
+            password.insert(idx, c);
+        }
+    }
+
+    Ok(String::from_utf8(password).expect("policy only contains ASCII"))
+}
+
+// generate 返回密码、熵和所使用的策略描述，调用 count 次生成对应数量的记录
+pub fn process_gen_pass_records(
+    count: u32,
+    mut generate: impl FnMut() -> anyhow::Result<(String, f64, String)>,
+) -> anyhow::Result<Vec<GenPassRecord>> {
+    (0..count)
+        .map(|_| {
+            let (password, entropy, policy) = generate()?;
+            let score = zxcvbn(&password, &[]).score().into();
+            Ok(GenPassRecord {
+                password,
+                score,
+                entropy: (entropy * 10.0).round() / 10.0,
+                policy,
+            })
+        })
+        .collect()
+}
+
+// plain 模式每行一个密码，json 输出数组，csv 带 header
+pub fn write_gen_pass_records(
+    records: &[GenPassRecord],
+    format: GenPassOutputFormat,
+    mut writer: impl Write,
+) -> anyhow::Result<()> {
+    match format {
+        GenPassOutputFormat::Plain => {
+            for record in records {
+                writeln!(writer, "{}", record.password)?;
+            }
+        }
+        GenPassOutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, records)?;
+            writeln!(writer)?;
+        }
+        GenPassOutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut writer);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// thread_rng 是用户态的 CSPRNG，OsRng 每次都直接从操作系统获取随机数，便于审计
+pub fn new_rng(source: RngSource) -> Box<dyn RngCore> {
+    match source {
+        RngSource::Thread => Box::new(rand::thread_rng()),
+        RngSource::Os => Box::new(OsRng),
+    }
+}
+
+// no_repeat 时跳过已经使用过的字符
+fn pick(chars: &[u8], used: &[u8], no_repeat: bool, rng: &mut impl Rng) -> Option<u8> {
+    if no_repeat {
+        let available = chars
+            .iter()
+            .filter(|c| !used.contains(c))
+            .collect::<Vec<_>>();
+        available.choose(rng).map(|c| **c)
+    } else {
+        chars.choose(rng).copied()
+    }
+}
 
-    let password = String::from_utf8(password)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        process::password_policy::{CONSONANTS, VOWELS},
+        CharClass,
+    };
+    use proptest::prelude::*;
 
-    Ok(password)
+    fn sample_records() -> anyhow::Result<Vec<GenPassRecord>> {
+        let mut n = 0;
+        process_gen_pass_records(3, || {
+            n += 1;
+            Ok((format!("pa,ss\"{}", n), 12.345, "length=7".to_string()))
+        })
+    }
+
+    #[test]
+    fn test_gen_pass_records() -> anyhow::Result<()> {
+        let records = sample_records()?;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].password, "pa,ss\"3");
+        assert_eq!(records[0].entropy, 12.3);
+        assert!(records[0].score <= 4);
+
+        let mut calls = 0;
+        let result = process_gen_pass_records(5, || {
+            calls += 1;
+            anyhow::bail!("policy error")
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_gen_pass_records() -> anyhow::Result<()> {
+        let records = sample_records()?;
+
+        let mut json = Vec::new();
+        write_gen_pass_records(&records, GenPassOutputFormat::Json, &mut json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json)?;
+        let json = json.as_array().unwrap();
+        assert_eq!(json.len(), 3);
+        let mut fields = json[0].as_object().unwrap().keys().collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(fields, ["entropy", "password", "policy", "score"]);
+        assert_eq!(json[0]["password"], "pa,ss\"1");
+
+        // 密码中的逗号和引号需要按 CSV 规则加引号转义
+        let mut csv = Vec::new();
+        write_gen_pass_records(&records, GenPassOutputFormat::Csv, &mut csv)?;
+        let csv = String::from_utf8(csv)?;
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("password,score,entropy,policy"));
+        assert!(lines.next().unwrap().starts_with("\"pa,ss\"\"1\","));
+        assert_eq!(lines.count(), 2);
+
+        let mut plain = Vec::new();
+        write_gen_pass_records(&records, GenPassOutputFormat::Plain, &mut plain)?;
+        assert_eq!(String::from_utf8(plain)?, "pa,ss\"1\npa,ss\"2\npa,ss\"3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_os_rng() -> anyhow::Result<()> {
+        let policy = PasswordPolicy::from_flags(32, true, true, true, true);
+        let password = process_gen_pass(&policy, RngSource::Os)?;
+        assert_eq!(password.len(), 32);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_policy() -> anyhow::Result<()> {
+        let policy = PasswordPolicy {
+            length: 10,
+            classes: vec![
+                CharClass::new("lower", "abcdefghijklmnopqrstuvwxyz", 0),
+                CharClass::new("number", "0123456789", 2),
+                CharClass {
+                    leading: false,
+                    ..CharClass::new("symbol", "-_.", 2)
+                },
+            ],
+            no_repeat: true,
+            pronounceable: false,
+        };
+
+        for _ in 0..100 {
+            let password = process_gen_pass(&policy, RngSource::Thread)?;
+            let bytes = password.as_bytes();
+            assert_eq!(bytes.len(), 10);
+            assert!(bytes.iter().filter(|c| c.is_ascii_digit()).count() >= 2);
+            assert!(bytes.iter().filter(|c| b"-_.".contains(c)).count() >= 2);
+            assert!(!b"-_.".contains(&bytes[0]));
+
+            let mut unique = bytes.to_vec();
+            unique.sort_unstable();
+            unique.dedup();
+            assert_eq!(unique.len(), bytes.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_pronounceable() -> anyhow::Result<()> {
+        let mut policy = PasswordPolicy::from_flags(12, true, true, true, false);
+        policy.pronounceable = true;
+
+        for _ in 0..100 {
+            let password = process_gen_pass(&policy, RngSource::Thread)?;
+            assert_eq!(password.len(), 12);
+            assert_eq!(password.bytes().filter(u8::is_ascii_digit).count(), 1);
+            assert_eq!(password.bytes().filter(u8::is_ascii_uppercase).count(), 1);
+
+            // 去掉数字后辅音和元音交替出现
+            let letters = password
+                .bytes()
+                .filter(u8::is_ascii_alphabetic)
+                .map(|c| c.to_ascii_lowercase())
+                .collect::<Vec<_>>();
+            for (i, c) in letters.iter().enumerate() {
+                let chars = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+                assert!(chars.as_bytes().contains(c));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_pronounceable_no_lowercase() -> anyhow::Result<()> {
+        let mut policy = PasswordPolicy::from_flags(12, true, false, true, true);
+        policy.pronounceable = true;
+
+        for _ in 0..100 {
+            let password = process_gen_pass(&policy, RngSource::Thread)?;
+            assert_eq!(password.len(), 12);
+            assert!(!password.bytes().any(|c| c.is_ascii_lowercase()));
+            // 大写字母中没有 I 和 O
+            assert!(!password.contains(['I', 'O']));
+            assert_eq!(password.bytes().filter(u8::is_ascii_uppercase).count(), 10);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_pass_pin() -> anyhow::Result<()> {
+        let policy = PasswordPolicy::preset("pin").unwrap();
+        let pin = process_gen_pass(&policy, RngSource::Thread)?;
+        assert_eq!(pin.len(), 6);
+        assert!(pin.bytes().all(|c| c.is_ascii_digit()));
+        Ok(())
+    }
+
+    proptest! {
+        // 任意长度和字符类组合：要么生成的密码包含每个开启的类，要么返回对应的错误
+        #[test]
+        fn prop_gen_pass_contains_enabled_classes(
+            length in 0usize..600,
+            upper: bool,
+            lower: bool,
+            number: bool,
+            symbol: bool,
+        ) {
+            let policy = PasswordPolicy::from_flags(length, upper, lower, number, symbol);
+            let enabled = [upper, lower, number, symbol].iter().filter(|e| **e).count();
+
+            match process_gen_pass(&policy, RngSource::Thread) {
+                Ok(password) => {
+                    prop_assert_eq!(password.len(), length);
+                    for class in &policy.classes {
+                        prop_assert!(password.bytes().any(|c| class.chars.as_bytes().contains(&c)));
+                    }
+                }
+                Err(PolicyError::NoClasses) => prop_assert_eq!(enabled, 0),
+                Err(PolicyError::InvalidLength(0)) => prop_assert_eq!(length, 0),
+                Err(PolicyError::TooShort { required, .. }) => {
+                    prop_assert_eq!(required, enabled);
+                    prop_assert!(length < enabled);
+                }
+                Err(e) => prop_assert!(false, "unexpected error: {}", e),
+            }
+        }
+    }
 }
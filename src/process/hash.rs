@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_256, Sha3_512};
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::{get_reader, HashAlgorithm};
+
+// 小于这个大小的文件多线程反而更慢，见 blake3 的 update_rayon 文档
+const RAYON_THRESHOLD: u64 = 128 * 1024;
+
+// 统一不同哈希算法的增量计算接口
+pub enum Hasher {
+    Sha1(Sha1),
+    Sha224(Sha224),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+    Sha3_256(Sha3_256),
+    Sha3_512(Sha3_512),
+    Md5(Md5),
+    Crc32(crc32fast::Hasher),
+    Blake3(Box<blake3::Hasher>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    // 文件不存在或读取失败
+    Unreadable,
+}
+
+#[derive(Debug)]
+pub struct CheckResult {
+    pub path: String,
+    pub status: CheckStatus,
+}
+
+impl Hasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+            HashAlgorithm::Sha224 => Self::Sha224(Sha224::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Sha384 => Self::Sha384(Sha384::new()),
+            HashAlgorithm::Sha512 => Self::Sha512(Sha512::new()),
+            HashAlgorithm::Sha3_256 => Self::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Sha3_512 => Self::Sha3_512(Sha3_512::new()),
+            HashAlgorithm::Md5 => Self::Md5(Md5::new()),
+            HashAlgorithm::Crc32 => Self::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(h) => h.update(data),
+            Self::Sha224(h) => h.update(data),
+            Self::Sha256(h) => h.update(data),
+            Self::Sha384(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+            Self::Sha3_256(h) => h.update(data),
+            Self::Sha3_512(h) => h.update(data),
+            Self::Md5(h) => h.update(data),
+            Self::Crc32(h) => h.update(data),
+            Self::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha1(h) => h.finalize().to_vec(),
+            Self::Sha224(h) => h.finalize().to_vec(),
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha384(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+            Self::Sha3_256(h) => h.finalize().to_vec(),
+            Self::Sha3_512(h) => h.finalize().to_vec(),
+            Self::Md5(h) => h.finalize().to_vec(),
+            // 和 cksum -a crc32b、zlib 一致，按大端序输出
+            Self::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            Self::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// 计算文件或标准输入的摘要，返回小写十六进制字符串
+pub fn process_hash(input: &str, algorithm: HashAlgorithm) -> Result<String> {
+    let digest = match algorithm {
+        // 较大的文件用内存映射并行计算 blake3
+        HashAlgorithm::Blake3
+            if input != "-" && std::fs::metadata(input)?.len() >= RAYON_THRESHOLD =>
+        {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_mmap_rayon(input)?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+        _ => {
+            let mut hasher = Hasher::new(algorithm);
+            io::copy(&mut get_reader(input)?, &mut hasher)?;
+            hasher.finalize()
+        }
+    };
+    Ok(to_hex(&digest))
+}
+
+// 和 sha256sum 一样，文件名含有 \ 或换行时整行以 \ 开头，文件名中的 \ 和换行转义
+pub fn format_sum_line(digest: &str, path: &str) -> String {
+    if path.contains(['\\', '\n']) {
+        let path = path.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("\\{}  {}", digest, path)
+    } else {
+        format!("{}  {}", digest, path)
+    }
+}
+
+// 校验 sha256sum 格式的文件：每行 <摘要>  <文件名>，二进制模式下文件名前带 *
+pub fn process_hash_check(sums: &str, algorithm: HashAlgorithm) -> Result<Vec<CheckResult>> {
+    let reader = BufReader::new(get_reader(sums)?);
+    let mut results = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (expected, path) = parse_sum_line(line)
+            .with_context(|| format!("Malformed checksum line {}: {}", idx + 1, line))?;
+
+        let status = match process_hash(&path, algorithm) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => CheckStatus::Ok,
+            Ok(_) => CheckStatus::Failed,
+            Err(_) => CheckStatus::Unreadable,
+        };
+        results.push(CheckResult { path, status });
+    }
+    Ok(results)
+}
+
+fn parse_sum_line(line: &str) -> Option<(&str, String)> {
+    // 以 \ 开头的行中文件名是转义过的
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (digest, rest) = line.split_once(' ')?;
+    if digest.is_empty() || !digest.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    // 文本模式是两个空格，二进制模式是空格加 *
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    if path.is_empty() {
+        return None;
+    }
+    if !escaped {
+        return Some((digest, path.to_string()));
+    }
+
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                'n' => unescaped.push('\n'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some((digest, unescaped))
+}
+
+pub(crate) fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_sum_line() {
+        assert_eq!(
+            parse_sum_line("abc123 *bin/a.out"),
+            Some(("abc123", "bin/a.out".to_string()))
+        );
+        assert_eq!(
+            parse_sum_line("\\abc123  dir\\\\name\\nx.txt"),
+            Some(("abc123", "dir\\name\nx.txt".to_string()))
+        );
+        // 没有 \ 前缀时文件名原样使用
+        assert_eq!(
+            parse_sum_line("abc123  a\\nb"),
+            Some(("abc123", "a\\nb".to_string()))
+        );
+        assert_eq!(parse_sum_line("\\abc123  bad\\t"), None);
+        assert_eq!(parse_sum_line("\\abc123  trailing\\"), None);
+    }
+
+    #[test]
+    fn test_process_hash() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc")?;
+        let path = path.to_str().unwrap();
+
+        let cases = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (
+                HashAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (HashAlgorithm::Crc32, "352441c2"),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(process_hash(path, algorithm)?, expected, "{}", algorithm);
+        }
+
+        // 多线程计算的结果和单线程一致
+        let big = dir.path().join("big.bin");
+        let data = (0..1_000_000u32)
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<_>>();
+        fs::write(&big, &data)?;
+        assert_eq!(
+            process_hash(big.to_str().unwrap(), HashAlgorithm::Blake3)?,
+            blake3::hash(&data).to_hex().to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hash_check() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+        fs::write(&a, "abc")?;
+        fs::write(&b, "tampered")?;
+        let sums = dir.path().join("sums.txt");
+        fs::write(
+            &sums,
+            format!(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  {}\n\
+                 ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad *{}\n\
+                 ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  {}/missing\n",
+                a.display(),
+                b.display(),
+                dir.path().display()
+            ),
+        )?;
+
+        let results = process_hash_check(sums.to_str().unwrap(), HashAlgorithm::Sha256)?;
+        let statuses = results.iter().map(|r| r.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                CheckStatus::Ok,
+                CheckStatus::Failed,
+                CheckStatus::Unreadable
+            ]
+        );
+
+        // 文件名含有 \ 或换行时 sha256sum 输出转义过的行
+        let odd = dir.path().join("a\\b\nc.txt");
+        fs::write(&odd, "abc")?;
+        let line = format_sum_line(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            odd.to_str().unwrap(),
+        );
+        assert!(line.starts_with('\\') && !line.contains('\n'));
+        fs::write(&sums, format!("{}\n", line))?;
+        let results = process_hash_check(sums.to_str().unwrap(), HashAlgorithm::Sha256)?;
+        assert_eq!(results[0].path, odd.to_str().unwrap());
+        assert_eq!(results[0].status, CheckStatus::Ok);
+
+        fs::write(&sums, "not a checksum line")?;
+        assert!(process_hash_check(sums.to_str().unwrap(), HashAlgorithm::Sha256).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use base64::Engine as _;
+use data_encoding::HEXLOWER_PERMISSIVE;
+use hmac::{digest::KeyInit, Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use std::io::{self, Read};
+
+use super::{b64::engine, hash::to_hex, otp::now};
+use crate::{get_reader, Base64Format, HmacAlgorithm, SignatureEncoding, WebhookProvider};
+
+// 计算文件或标准输入的 HMAC，返回按 encoding 编码的签名
+pub fn process_hmac_sign(
+    input: &str,
+    key: &[u8],
+    algorithm: HmacAlgorithm,
+    encoding: SignatureEncoding,
+) -> Result<String> {
+    let mac = hmac_reader(algorithm, key, get_reader(input)?)?;
+    Ok(match encoding {
+        SignatureEncoding::Hex => to_hex(&mac),
+        SignatureEncoding::Base64 => engine(Base64Format::Standard).encode(mac),
+    })
+}
+
+// 常量时间比较，签名格式错误时返回错误而不是 false
+pub fn process_hmac_verify(
+    input: &str,
+    key: &[u8],
+    algorithm: HmacAlgorithm,
+    encoding: SignatureEncoding,
+    signature: &str,
+) -> Result<bool> {
+    let signature = signature.trim();
+    let expected = match encoding {
+        SignatureEncoding::Hex => HEXLOWER_PERMISSIVE.decode(signature.as_bytes())?,
+        SignatureEncoding::Base64 => engine(Base64Format::Auto).decode(signature)?,
+    };
+    hmac_verify(algorithm, key, get_reader(input)?, &[expected])
+}
+
+// 校验 GitHub 的 X-Hub-Signature-256 或 Stripe 的 Stripe-Signature 请求头，
+// payload 必须是收到的原始请求体，tolerance 为 0 时不检查 Stripe 的时间戳
+pub fn process_webhook_verify(
+    input: &str,
+    secret: &[u8],
+    provider: WebhookProvider,
+    header: &str,
+    tolerance: u64,
+) -> Result<bool> {
+    verify_webhook(
+        get_reader(input)?,
+        secret,
+        provider,
+        header,
+        tolerance,
+        now(),
+    )
+}
+
+fn verify_webhook(
+    payload: impl Read,
+    secret: &[u8],
+    provider: WebhookProvider,
+    header: &str,
+    tolerance: u64,
+    now: u64,
+) -> Result<bool> {
+    // 允许直接粘贴整行请求头，例如 "X-Hub-Signature-256: sha256=..."
+    let header = header.split_once(':').map_or(header, |(_, v)| v).trim();
+
+    match provider {
+        WebhookProvider::Github => {
+            let Some((algorithm, signature)) = header.split_once('=') else {
+                anyhow::bail!("Expected a header like sha256=<hex>");
+            };
+            let algorithm = match algorithm {
+                "sha256" => HmacAlgorithm::Sha256,
+                // 旧的 X-Hub-Signature 请求头
+                "sha1" => HmacAlgorithm::Sha1,
+                v => anyhow::bail!("Unsupported GitHub signature algorithm: {}", v),
+            };
+            let expected = HEXLOWER_PERMISSIVE.decode(signature.as_bytes())?;
+            hmac_verify(algorithm, secret, payload, &[expected])
+        }
+        WebhookProvider::Stripe => {
+            // t=<时间戳>,v1=<签名>[,v1=<签名>...]，轮换密钥期间会有多个 v1
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for item in header.split(',') {
+                match item.trim().split_once('=') {
+                    Some(("t", t)) => timestamp = Some(t.parse::<u64>()?),
+                    Some(("v1", v)) => signatures.push(HEXLOWER_PERMISSIVE.decode(v.as_bytes())?),
+                    _ => {}
+                }
+            }
+            let Some(timestamp) = timestamp else {
+                anyhow::bail!("Stripe signature header has no timestamp");
+            };
+            if signatures.is_empty() {
+                anyhow::bail!("Stripe signature header has no v1 signature");
+            }
+            if tolerance > 0 && now.abs_diff(timestamp) > tolerance {
+                anyhow::bail!(
+                    "Timestamp {} is outside the tolerance of {} seconds",
+                    timestamp,
+                    tolerance
+                );
+            }
+
+            // 签名内容是 "<时间戳>.<请求体>"
+            let prefix = format!("{}.", timestamp);
+            hmac_verify(
+                HmacAlgorithm::Sha256,
+                secret,
+                prefix.as_bytes().chain(payload),
+                &signatures,
+            )
+        }
+    }
+}
+
+fn hmac_reader(algorithm: HmacAlgorithm, key: &[u8], reader: impl Read) -> Result<Vec<u8>> {
+    Ok(match algorithm {
+        HmacAlgorithm::Sha1 => mac_bytes(mac_reader::<Hmac<Sha1>>(key, reader)?),
+        HmacAlgorithm::Sha256 => mac_bytes(mac_reader::<Hmac<Sha256>>(key, reader)?),
+        HmacAlgorithm::Sha384 => mac_bytes(mac_reader::<Hmac<Sha384>>(key, reader)?),
+        HmacAlgorithm::Sha512 => mac_bytes(mac_reader::<Hmac<Sha512>>(key, reader)?),
+    })
+}
+
+// 任意一个候选签名匹配即通过，比较由 Mac::verify_slice 以常量时间完成
+fn hmac_verify(
+    algorithm: HmacAlgorithm,
+    key: &[u8],
+    reader: impl Read,
+    signatures: &[Vec<u8>],
+) -> Result<bool> {
+    Ok(match algorithm {
+        HmacAlgorithm::Sha1 => mac_verify(mac_reader::<Hmac<Sha1>>(key, reader)?, signatures),
+        HmacAlgorithm::Sha256 => mac_verify(mac_reader::<Hmac<Sha256>>(key, reader)?, signatures),
+        HmacAlgorithm::Sha384 => mac_verify(mac_reader::<Hmac<Sha384>>(key, reader)?, signatures),
+        HmacAlgorithm::Sha512 => mac_verify(mac_reader::<Hmac<Sha512>>(key, reader)?, signatures),
+    })
+}
+
+fn mac_verify<M: Mac + Clone>(mac: M, signatures: &[Vec<u8>]) -> bool {
+    // 检查完所有签名再返回
+    signatures
+        .iter()
+        .fold(false, |ok, sig| mac.clone().verify_slice(sig).is_ok() | ok)
+}
+
+fn mac_reader<M: Mac + KeyInit>(key: &[u8], mut reader: impl Read) -> io::Result<M> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        mac.update(&buf[..n]);
+    }
+    Ok(mac)
+}
+
+pub(crate) fn hmac_digest<M: Mac + KeyInit>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    mac_bytes(mac_reader::<M>(key, msg).expect("reading from a slice never fails"))
+}
+
+fn mac_bytes<M: Mac>(mac: M) -> Vec<u8> {
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_hmac_sign_verify() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("msg.txt");
+        fs::write(&path, "The quick brown fox jumps over the lazy dog")?;
+        let path = path.to_str().unwrap();
+
+        // 维基百科 HMAC 词条中的示例
+        let sig = process_hmac_sign(path, b"key", HmacAlgorithm::Sha256, SignatureEncoding::Hex)?;
+        assert_eq!(
+            sig,
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert!(process_hmac_verify(
+            path,
+            b"key",
+            HmacAlgorithm::Sha256,
+            SignatureEncoding::Hex,
+            &sig.to_uppercase()
+        )?);
+        assert!(!process_hmac_verify(
+            path,
+            b"other",
+            HmacAlgorithm::Sha256,
+            SignatureEncoding::Hex,
+            &sig
+        )?);
+
+        let sig = process_hmac_sign(
+            path,
+            b"key",
+            HmacAlgorithm::Sha512,
+            SignatureEncoding::Base64,
+        )?;
+        assert!(process_hmac_verify(
+            path,
+            b"key",
+            HmacAlgorithm::Sha512,
+            SignatureEncoding::Base64,
+            &sig
+        )?);
+        assert!(process_hmac_verify(
+            path,
+            b"key",
+            HmacAlgorithm::Sha512,
+            SignatureEncoding::Hex,
+            "zz"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_webhook_verify() -> Result<()> {
+        // GitHub 文档中的示例
+        let payload = b"Hello, World!".as_slice();
+        let header = "X-Hub-Signature-256: sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_webhook(
+            payload,
+            b"It's a Secret to Everybody",
+            WebhookProvider::Github,
+            header,
+            0,
+            0
+        )?);
+        assert!(!verify_webhook(
+            payload,
+            b"wrong",
+            WebhookProvider::Github,
+            header,
+            0,
+            0
+        )?);
+
+        let body = br#"{"id":"evt_1"}"#;
+        let mac = hmac_digest::<Hmac<Sha256>>(b"whsec_test", b"1700000000.{\"id\":\"evt_1\"}");
+        let header = format!("t=1700000000,v1=00ff,v1={},v0=abcd", to_hex(&mac));
+        let verify = |now| {
+            verify_webhook(
+                body.as_slice(),
+                b"whsec_test",
+                WebhookProvider::Stripe,
+                &header,
+                300,
+                now,
+            )
+        };
+        assert!(verify(1_700_000_100)?);
+        // 超过容忍时间的请求可能是重放攻击
+        assert!(verify(1_700_000_301).is_err());
+        assert!(verify_webhook(
+            body.as_slice(),
+            b"whsec_test",
+            WebhookProvider::Stripe,
+            "v1=00",
+            0,
+            0
+        )
+        .is_err());
+        Ok(())
+    }
+}
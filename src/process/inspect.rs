@@ -0,0 +1,411 @@
+use anyhow::Result;
+use base64::Engine as _;
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+
+use super::b64::engine;
+use crate::{get_reader, Base64Format};
+
+// 解压后最多检查的字节数，避免压缩炸弹
+const MAX_INFLATE: u64 = 64 * 1024 * 1024;
+// gzip 套 gzip 之类的嵌套最多展开几层
+const MAX_DEPTH: usize = 4;
+
+#[derive(Debug)]
+pub struct InspectReport {
+    // 输入使用的编码，例如 hex、base64 (urlsafe)、gzip
+    pub encoding: String,
+    pub size: usize,
+    pub kind: &'static str,
+    pub details: Vec<String>,
+    // 可读的内容，JSON 会格式化输出
+    pub content: Option<String>,
+    // 压缩数据解压后的检查结果
+    pub inner: Option<Box<InspectReport>>,
+}
+
+// 自动识别输入的编码（hex、各种 base64、PEM），解码后根据魔数判断数据类型
+pub fn process_inspect(input: &str) -> Result<InspectReport> {
+    let mut data = Vec::new();
+    get_reader(input)?.read_to_end(&mut data)?;
+
+    let (encoding, decoded) = decode_input(&data);
+    Ok(inspect(encoding, decoded, 0))
+}
+
+fn decode_input(data: &[u8]) -> (String, Vec<u8>) {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return ("raw".to_string(), data.to_vec());
+    };
+    let compact = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    if compact.is_empty() {
+        return ("raw".to_string(), data.to_vec());
+    }
+
+    // 纯十六进制字符优先按 hex 解码，允许 de:ad:be:ef 这样的分隔符
+    let hex = compact
+        .iter()
+        .filter(|c| !matches!(c, b':' | b'-' | b','))
+        .copied()
+        .collect::<Vec<_>>();
+    if hex.len() % 2 == 0 && hex.iter().all(u8::is_ascii_hexdigit) {
+        if let Ok(decoded) = data_encoding::HEXLOWER_PERMISSIVE.decode(&hex) {
+            return ("hex".to_string(), decoded);
+        }
+    }
+
+    if compact
+        .iter()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'/' | b'-' | b'_' | b'='))
+    {
+        let urlsafe = compact.iter().any(|c| matches!(c, b'-' | b'_'));
+        // 长度是 4 的倍数时带不带填充都一样，按带填充的格式报告
+        let padded = compact.len() % 4 == 0;
+        let format = match (urlsafe, padded) {
+            (false, true) => Base64Format::Standard,
+            (false, false) => Base64Format::StandardNoPad,
+            (true, true) => Base64Format::UrlSafePad,
+            (true, false) => Base64Format::UrlSafe,
+        };
+        if let Ok(decoded) = engine(Base64Format::Auto).decode(normalize_base64(&compact)) {
+            return (format!("base64 ({})", format), decoded);
+        }
+    }
+
+    ("raw".to_string(), data.to_vec())
+}
+
+fn normalize_base64(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .map(|&c| match c {
+            b'-' => b'+',
+            b'_' => b'/',
+            c => c,
+        })
+        .collect()
+}
+
+fn inspect(encoding: String, data: Vec<u8>, depth: usize) -> InspectReport {
+    let mut report = InspectReport {
+        encoding,
+        size: data.len(),
+        kind: "unknown binary",
+        details: Vec::new(),
+        content: None,
+        inner: None,
+    };
+
+    match data.as_slice() {
+        [0x1f, 0x8b, ..] => {
+            report.kind = "gzip";
+            let mut inflated = Vec::new();
+            match MultiGzDecoder::new(data.as_slice())
+                .take(MAX_INFLATE)
+                .read_to_end(&mut inflated)
+            {
+                Ok(_) if depth < MAX_DEPTH => {
+                    report.inner = Some(Box::new(inspect("gzip".into(), inflated, depth + 1)))
+                }
+                Ok(_) => report.details.push("nested too deeply".into()),
+                Err(e) => report.details.push(format!("corrupt gzip stream: {}", e)),
+            }
+        }
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => {
+            report.kind = "zstd";
+            let mut inflated = Vec::new();
+            let decoded = zstd::stream::read::Decoder::new(data.as_slice())
+                .and_then(|decoder| decoder.take(MAX_INFLATE).read_to_end(&mut inflated));
+            match decoded {
+                Ok(_) if depth < MAX_DEPTH => {
+                    report.inner = Some(Box::new(inspect("zstd".into(), inflated, depth + 1)))
+                }
+                Ok(_) => report.details.push("nested too deeply".into()),
+                Err(e) => report.details.push(format!("corrupt zstd stream: {}", e)),
+            }
+        }
+        [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, rest @ ..] => {
+            report.kind = "PNG image";
+            // 第一个块是 IHDR，宽高是大端序的 u32
+            if rest.len() >= 16 && &rest[4..8] == b"IHDR" {
+                let width = u32::from_be_bytes(rest[8..12].try_into().unwrap());
+                let height = u32::from_be_bytes(rest[12..16].try_into().unwrap());
+                report.details.push(format!("{}x{}", width, height));
+            }
+        }
+        [0xff, 0xd8, 0xff, ..] => report.kind = "JPEG image",
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', w0, w1, h0, h1, ..] => {
+            report.kind = "GIF image";
+            let width = u16::from_le_bytes([*w0, *w1]);
+            let height = u16::from_le_bytes([*h0, *h1]);
+            report.details.push(format!("{}x{}", width, height));
+        }
+        [b'%', b'P', b'D', b'F', b'-', rest @ ..] => {
+            report.kind = "PDF document";
+            let version = rest
+                .iter()
+                .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                .map(|&c| c as char)
+                .collect::<String>();
+            report.details.push(format!("version {}", version));
+        }
+        [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => {
+            report.kind = "ZIP archive";
+            // 本地文件头中偏移 26 处是文件名长度，文件名从偏移 30 开始
+            if data.len() >= 30 {
+                let len = u16::from_le_bytes([data[26], data[27]]) as usize;
+                if let Some(name) = data.get(30..30 + len) {
+                    report
+                        .details
+                        .push(format!("first entry: {}", String::from_utf8_lossy(name)));
+                }
+            }
+        }
+        _ if der_sequence_len(&data) == Some(data.len()) => {
+            report.kind = "DER (ASN.1)";
+            report.details.push(describe_der(&data).into());
+        }
+        _ => match std::str::from_utf8(&data) {
+            Ok(text) if is_text(text) => inspect_text(&mut report, text, depth),
+            _ => match protobuf_fields(&data) {
+                Some(fields) => {
+                    report.kind = "protobuf (probably)";
+                    report.details.push(format!("field numbers: {:?}", fields));
+                }
+                None => {
+                    let head = data.iter().take(16).map(|b| format!("{:02x}", b));
+                    report.details.push(format!(
+                        "first bytes: {}",
+                        head.collect::<Vec<_>>().join(" ")
+                    ));
+                }
+            },
+        },
+    }
+    report
+}
+
+fn inspect_text(report: &mut InspectReport, text: &str, depth: usize) {
+    let trimmed = text.trim();
+    if let Some((header, claims)) = parse_jwt(trimmed) {
+        report.kind = "JWT";
+        for key in ["alg", "typ", "kid"] {
+            if let Some(value) = header.get(key).and_then(|v| v.as_str()) {
+                report.details.push(format!("{}: {}", key, value));
+            }
+        }
+        report.content = Some(format!(
+            "{}\n{}",
+            serde_json::to_string_pretty(&header).unwrap_or_default(),
+            serde_json::to_string_pretty(&claims).unwrap_or_default()
+        ));
+    } else if let Some(label) = trimmed
+        .strip_prefix("-----BEGIN ")
+        .and_then(|rest| rest.split_once("-----"))
+        .map(|(label, _)| label)
+    {
+        report.kind = "PEM";
+        report.details.push(label.to_string());
+        report.content = Some(text.to_string());
+        // 只解码第一个块，跳过 Proc-Type 之类的头部字段
+        let body = trimmed
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.starts_with("-----END "))
+            .filter(|line| !line.contains(':'))
+            .flat_map(|line| line.trim().bytes())
+            .collect::<Vec<_>>();
+        if let (Ok(der), true) = (engine(Base64Format::Auto).decode(body), depth < MAX_DEPTH) {
+            report.inner = Some(Box::new(inspect("pem".into(), der, depth + 1)));
+        }
+    } else if let Ok(json) = serde_json::from_str::<serde_json::Value>(trimmed) {
+        report.kind = "JSON";
+        match &json {
+            serde_json::Value::Object(map) => report
+                .details
+                .push(format!("object with {} keys", map.len())),
+            serde_json::Value::Array(items) => report
+                .details
+                .push(format!("array of {} items", items.len())),
+            _ => report.details.push("scalar value".into()),
+        }
+        report.content = serde_json::to_string_pretty(&json).ok();
+    } else {
+        report.kind = "UTF-8 text";
+        report
+            .details
+            .push(format!("{} lines", text.lines().count()));
+        report.content = Some(text.to_string());
+    }
+}
+
+// 除了常见的空白字符外不含控制字符
+fn is_text(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+}
+
+fn parse_jwt(text: &str) -> Option<(serde_json::Value, serde_json::Value)> {
+    let mut parts = text.split('.');
+    let (header, claims, _signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let decode = |part: &str| -> Option<serde_json::Value> {
+        let bytes = engine(Base64Format::Auto)
+            .decode(normalize_base64(part.as_bytes()))
+            .ok()?;
+        serde_json::from_slice(&bytes).ok()
+    };
+    let header = decode(header)?;
+    header.get("alg")?;
+    Some((header, decode(claims)?))
+}
+
+// 0x30 开头的 SEQUENCE，返回头部加内容的总长度
+fn der_sequence_len(data: &[u8]) -> Option<usize> {
+    let (&tag, rest) = data.split_first()?;
+    if tag != 0x30 {
+        return None;
+    }
+    let (header, len) = der_length(rest)?;
+    Some(1 + header + len)
+}
+
+// 返回长度字段占用的字节数和内容长度
+fn der_length(data: &[u8]) -> Option<(usize, usize)> {
+    let (&first, rest) = data.split_first()?;
+    if first < 0x80 {
+        return Some((1, first as usize));
+    }
+    let n = (first & 0x7f) as usize;
+    if n == 0 || n > 4 || rest.len() < n {
+        return None;
+    }
+    let len = rest[..n]
+        .iter()
+        .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+    Some((1 + n, len))
+}
+
+fn describe_der(data: &[u8]) -> &'static str {
+    // 证书的 tbsCertificate 是 SEQUENCE，第一个元素是 [0] 显式标记的版本号
+    let tbs = der_length(&data[1..])
+        .map(|(header, _)| &data[1 + header..])
+        .filter(|tbs| tbs.first() == Some(&0x30));
+    let version = tbs
+        .and_then(|tbs| der_length(&tbs[1..]).map(|(header, _)| &tbs[1 + header..]))
+        .and_then(|body| body.first());
+    match version {
+        Some(0xa0) => "X.509 certificate",
+        _ => "SEQUENCE, e.g. a key, CSR or signature",
+    }
+}
+
+// 按 protobuf 的 wire format 解析，全部字节都能解析时返回出现过的字段编号
+fn protobuf_fields(mut data: &[u8]) -> Option<Vec<u64>> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let (field, wire_type) = (key >> 3, key & 0x7);
+        if field == 0 || field > 536_870_911 {
+            return None;
+        }
+        let skip = match wire_type {
+            0 => {
+                read_varint(&mut data)?;
+                0
+            }
+            1 => 8,
+            2 => usize::try_from(read_varint(&mut data)?).ok()?,
+            5 => 4,
+            _ => return None,
+        };
+        data = data.get(skip..)?;
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+    (!fields.is_empty()).then_some(fields)
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let (&b, rest) = data.split_first()?;
+        *data = rest;
+        value |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn inspect_str(input: &str) -> InspectReport {
+        let (encoding, decoded) = decode_input(input.as_bytes());
+        inspect(encoding, decoded, 0)
+    }
+
+    #[test]
+    fn test_inspect_detects_payloads() -> Result<()> {
+        let png = engine(Base64Format::Standard)
+            .encode(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x01\x00\x00\x00\x00\x80");
+        let report = inspect_str(&png);
+        assert_eq!(report.encoding, "base64 (standard)");
+        assert_eq!(
+            (report.kind, report.details[0].as_str()),
+            ("PNG image", "256x128")
+        );
+
+        let report = inspect_str("25:50:44:46:2d:31:2e:37");
+        assert_eq!(report.encoding, "hex");
+        assert_eq!(report.kind, "PDF document");
+
+        // 字段 1 varint 150，字段 2 字符串 "hi"
+        let report = inspect_str(&engine(Base64Format::UrlSafe).encode(b"\x08\x96\x01\x12\x02hi"));
+        assert_eq!(report.kind, "protobuf (probably)");
+
+        let report =
+            inspect_str(&engine(Base64Format::Standard).encode([0x30, 0x03, 0x02, 0x01, 0x05]));
+        assert_eq!(report.kind, "DER (ASN.1)");
+
+        let report =
+            inspect_str("-----BEGIN PUBLIC KEY-----\nMAMCAQU=\n-----END PUBLIC KEY-----\n");
+        assert_eq!(
+            (report.kind, report.details[0].as_str()),
+            ("PEM", "PUBLIC KEY")
+        );
+        assert_eq!(report.inner.unwrap().kind, "DER (ASN.1)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect_gzip_json_and_jwt() -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(br#"{"user":"rcli","roles":["admin"]}"#)?;
+        let report = inspect_str(&engine(Base64Format::Mime).encode(encoder.finish()?));
+        assert_eq!(report.kind, "gzip");
+        let inner = report.inner.expect("gzip payload");
+        assert_eq!(inner.kind, "JSON");
+        assert_eq!(inner.details, ["object with 2 keys"]);
+        assert!(inner.content.unwrap().contains("\n  \"user\": \"rcli\""));
+
+        let jwt = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0.sig";
+        let report = inspect_str(jwt);
+        assert_eq!(report.encoding, "raw");
+        assert_eq!(report.kind, "JWT");
+        assert_eq!(report.details, ["alg: HS256", "typ: JWT"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use super::{b64_dir::rel_path, hash::process_hash};
+use crate::{get_writer, HashAlgorithm};
+
+// 叶子和中间节点使用不同的前缀，避免把中间节点伪造成叶子
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+// 符号链接的叶子单独加前缀，文件内容恰好等于链接目标时也不会冲突
+const LINK_PREFIX: u8 = 0x02;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeManifest {
+    pub algorithm: String,
+    pub root: String,
+    // 按路径排序，路径相对于目录并统一使用 / 分隔
+    pub files: Vec<TreeEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TreeEntry {
+    pub path: String,
+    pub size: u64,
+    pub hash: String,
+    // 符号链接不会被跟随，记录链接目标，size 和 hash 对应目标路径本身
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct TreeDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub root: String,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+// 计算目录下每个文件的 blake3 哈希和 Merkle 根哈希，并以 JSON 写到 output，
+// output 位于目录中时不会把它自己算进去
+pub fn process_hash_tree(dir: &Path, output: &str) -> Result<TreeManifest> {
+    let mut writer = get_writer(output)?;
+    let skip = (output != "-")
+        .then(|| fs::canonicalize(output))
+        .transpose()?;
+
+    let files = scan_tree(dir, skip.as_deref())?;
+    let manifest = TreeManifest {
+        algorithm: HashAlgorithm::Blake3.to_string(),
+        root: merkle_root(&files)?,
+        files,
+    };
+    serde_json::to_writer_pretty(&mut writer, &manifest)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    Ok(manifest)
+}
+
+// 和 manifest 对比，列出新增、删除和修改过的文件；
+// root 是单独保存的可信根哈希，传入时 manifest 的根哈希必须和它一致
+pub fn process_verify_tree(dir: &Path, manifest: &str, root: Option<&str>) -> Result<TreeDiff> {
+    let expected: TreeManifest = serde_json::from_reader(
+        File::open(manifest).with_context(|| format!("Cannot open {}", manifest))?,
+    )?;
+    // 文件列表和记录的根哈希要自洽，这只能发现 manifest 损坏，
+    // 改了文件列表又重新计算根哈希的 manifest 需要靠 root 才能发现
+    if merkle_root(&expected.files)? != expected.root {
+        anyhow::bail!("Manifest root hash does not match its file list");
+    }
+    if let Some(root) = root {
+        if !root.eq_ignore_ascii_case(&expected.root) {
+            anyhow::bail!(
+                "Manifest root {} does not match the expected root {}",
+                expected.root,
+                root
+            );
+        }
+    }
+
+    let actual = scan_tree(dir, Some(&fs::canonicalize(manifest)?))?;
+    let mut diff = TreeDiff {
+        root: merkle_root(&actual)?,
+        ..Default::default()
+    };
+    let mut expected = expected
+        .files
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry))
+        .collect::<BTreeMap<_, _>>();
+    for entry in actual {
+        match expected.remove(&entry.path) {
+            None => diff.added.push(entry.path),
+            Some(old) if old != entry => diff.modified.push(entry.path),
+            Some(_) => {}
+        }
+    }
+    diff.removed = expected.into_keys().collect();
+
+    Ok(diff)
+}
+
+fn scan_tree(dir: &Path, skip: Option<&Path>) -> Result<Vec<TreeEntry>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let path: PathBuf = entry.path().into();
+        // WalkDir 默认不跟随符号链接，链接本身作为一项记录下来，避免静默丢掉
+        if entry.path_is_symlink() {
+            let target = fs::read_link(&path)?;
+            let target = target
+                .to_str()
+                .with_context(|| format!("Non UTF-8 link target: {}", target.display()))?
+                .to_string();
+            files.push(TreeEntry {
+                path: rel_path(path.strip_prefix(dir)?),
+                size: target.len() as u64,
+                hash: blake3::hash(target.as_bytes()).to_hex().to_string(),
+                link: Some(target),
+            });
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if skip.is_some_and(|skip| fs::canonicalize(entry.path()).ok().as_deref() == Some(skip)) {
+            continue;
+        }
+        files.push(TreeEntry {
+            path: rel_path(path.strip_prefix(dir)?),
+            size: entry.metadata()?.len(),
+            hash: process_hash(
+                path.to_str()
+                    .with_context(|| format!("Non UTF-8 path: {}", path.display()))?,
+                HashAlgorithm::Blake3,
+            )?,
+            link: None,
+        });
+    }
+    // sort_by_file_name 按目录逐层排序，和按完整路径排序不完全一致
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+// 叶子是 blake3(0x00 || 路径 || 0x00 || 文件哈希)，符号链接的叶子再加上 0x02 前缀，
+// 中间节点是 blake3(0x01 || 左 || 右)，
+// 奇数个节点时最后一个直接进入上一层，空目录的根是 blake3("")
+fn merkle_root(files: &[TreeEntry]) -> Result<String> {
+    let mut level = files
+        .iter()
+        .map(|entry| {
+            let hash = blake3::Hash::from_hex(&entry.hash)?;
+            let mut hasher = blake3::Hasher::new();
+            if entry.link.is_some() {
+                hasher.update(&[LINK_PREFIX]);
+            }
+            hasher.update(&[LEAF_PREFIX]);
+            hasher.update(entry.path.as_bytes());
+            hasher.update(&[LEAF_PREFIX]);
+            hasher.update(hash.as_bytes());
+            Ok(hasher.finalize())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if level.is_empty() {
+        return Ok(blake3::hash(b"").to_hex().to_string());
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&[NODE_PREFIX]);
+                    hasher.update(left.as_bytes());
+                    hasher.update(right.as_bytes());
+                    hasher.finalize()
+                }
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    Ok(level[0].to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_tree_and_verify() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path();
+        fs::create_dir_all(root.join("assets/css"))?;
+        fs::write(root.join("index.html"), "<html></html>")?;
+        fs::write(root.join("assets/app.js"), "console.log(1)")?;
+        fs::write(root.join("assets/css/site.css"), "body {}")?;
+
+        // manifest 写在目录中，不会包含它自己
+        let manifest_path = root.join("manifest.json");
+        let manifest_path = manifest_path.to_str().unwrap();
+        let manifest = process_hash_tree(root, manifest_path)?;
+        let paths = manifest
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["assets/app.js", "assets/css/site.css", "index.html"]
+        );
+
+        let diff = process_verify_tree(root, manifest_path, None)?;
+        assert!(diff.is_empty());
+        assert_eq!(diff.root, manifest.root);
+
+        fs::write(root.join("index.html"), "<html>changed</html>")?;
+        fs::remove_file(root.join("assets/app.js"))?;
+        fs::write(root.join("new.txt"), "new")?;
+        let diff = process_verify_tree(root, manifest_path, None)?;
+        assert_eq!(diff.modified, ["index.html"]);
+        assert_eq!(diff.removed, ["assets/app.js"]);
+        assert_eq!(diff.added, ["new.txt"]);
+        assert_ne!(diff.root, manifest.root);
+
+        // 只改文件哈希而不更新根哈希，manifest 自身就不一致
+        let mut tampered = manifest;
+        let trusted_root = tampered.root.clone();
+        tampered.files[0].hash = blake3::hash(b"forged").to_hex().to_string();
+        fs::write(manifest_path, serde_json::to_string(&tampered)?)?;
+        assert!(process_verify_tree(root, manifest_path, None).is_err());
+
+        // 连根哈希一起重新计算的 manifest 是自洽的，只有和可信根哈希对比才能发现
+        tampered.root = merkle_root(&tampered.files)?;
+        fs::write(manifest_path, serde_json::to_string(&tampered)?)?;
+        assert!(process_verify_tree(root, manifest_path, None).is_ok());
+        assert!(process_verify_tree(root, manifest_path, Some(&trusted_root)).is_err());
+        // 根哈希比较不区分大小写
+        assert!(
+            process_verify_tree(root, manifest_path, Some(&tampered.root.to_uppercase())).is_ok()
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_tree_records_symlinks() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a")?;
+        std::os::unix::fs::symlink("a.txt", root.join("link"))?;
+
+        let files = scan_tree(root, None)?;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].path, "link");
+        assert_eq!(files[1].link.as_deref(), Some("a.txt"));
+
+        // 链接目标变化会被当成修改
+        let manifest_path = root.join("manifest.json");
+        let manifest_path = manifest_path.to_str().unwrap();
+        process_hash_tree(root, manifest_path)?;
+        fs::remove_file(root.join("link"))?;
+        std::os::unix::fs::symlink("b.txt", root.join("link"))?;
+        let diff = process_verify_tree(root, manifest_path, None)?;
+        assert_eq!(diff.modified, ["link"]);
+
+        // 和链接目标内容相同的普通文件不会被当成同一项
+        fs::remove_file(root.join("link"))?;
+        fs::write(root.join("link"), "b.txt")?;
+        let diff = process_verify_tree(root, manifest_path, None)?;
+        assert_eq!(diff.modified, ["link"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merkle_root() -> Result<()> {
+        let entry = |path: &str| TreeEntry {
+            path: path.to_string(),
+            size: 0,
+            hash: blake3::hash(path.as_bytes()).to_hex().to_string(),
+            link: None,
+        };
+        assert_eq!(merkle_root(&[])?, blake3::hash(b"").to_hex().to_string());
+        // 路径参与计算，交换两个文件的内容会改变根哈希
+        let (a, b) = (entry("a"), entry("b"));
+        let swapped = [
+            TreeEntry {
+                hash: b.hash.clone(),
+                ..a.clone()
+            },
+            TreeEntry {
+                hash: a.hash.clone(),
+                ..b.clone()
+            },
+        ];
+        assert_ne!(
+            merkle_root(&[a.clone(), b.clone()])?,
+            merkle_root(&swapped)?
+        );
+        assert_ne!(
+            merkle_root(&[a.clone(), b, entry("c")])?,
+            merkle_root(&[a])?
+        );
+        Ok(())
+    }
+}
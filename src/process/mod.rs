@@ -1,16 +1,57 @@
 mod b64;
+mod b64_dir;
+mod breach;
+mod codec;
+mod columnar;
 mod csv_convert;
+mod csv_sort;
+mod csv_watch;
 mod gen_pass;
+mod hash;
+mod hmac;
 mod http_serve;
+mod inspect;
 mod jwt;
+mod merkle;
+mod otp;
+mod passphrase;
+mod password_check;
+mod password_policy;
 mod text;
+mod token;
+mod transform;
 
-pub use b64::{process_decode, process_encode};
-pub use csv_convert::process_csv;
-pub use gen_pass::process_gen_pass;
+pub use b64::{
+    base64_stage, process_datauri_decode, process_datauri_encode, process_decode, process_encode,
+};
+pub use b64_dir::{process_decode_dir, process_encode_dir, Manifest, ManifestEntry, MANIFEST_NAME};
+pub use breach::BreachDb;
+pub use codec::{new_decoder, new_encoder, process_codec, pump, StreamCodec};
+pub use csv_convert::{process_csv, CsvConvertOptions};
+pub use csv_sort::{process_csv_sort, CsvSortOptions};
+pub use csv_watch::process_csv_watch;
+pub use gen_pass::{
+    process_gen_pass, process_gen_pass_records, write_gen_pass_records, GenPassRecord,
+};
+pub use hash::{
+    format_sum_line, process_hash, process_hash_check, CheckResult, CheckStatus, Hasher,
+};
+pub use hmac::{process_hmac_sign, process_hmac_verify, process_webhook_verify};
 pub use http_serve::process_http_serve;
+pub use inspect::{process_inspect, InspectReport};
 pub use jwt::{process_jwt_sign, process_jwt_verify};
+pub use merkle::{process_hash_tree, process_verify_tree, TreeDiff, TreeEntry, TreeManifest};
+pub use otp::{
+    process_otp_code, process_otp_qr, process_otp_secret, process_otp_uri, process_otp_verify,
+    OtpOptions,
+};
+pub use passphrase::{process_gen_passphrase, PassphraseOptions};
+pub use password_check::{process_password_check, CrackTimeReport, PasswordReport};
+pub use password_policy::{CharClass, PasswordPolicy, PolicyError, MAX_LENGTH};
 pub use text::{
-    process_text_decrypt, process_text_encrypt, process_text_generate, process_text_sign,
+    process_text_decrypt, process_text_decrypt_bytes, process_text_encrypt,
+    process_text_encrypt_bytes, process_text_generate, process_text_sign, process_text_sign_bytes,
     process_text_verify,
 };
+pub use token::{process_token_generate, process_token_verify, TokenOptions};
+pub use transform::process_transform;
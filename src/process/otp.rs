@@ -0,0 +1,237 @@
+use anyhow::Result;
+use data_encoding::BASE32_NOPAD;
+use hmac::Hmac;
+use percent_encoding::utf8_percent_encode;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use subtle::ConstantTimeEq;
+
+use super::{codec::URI_COMPONENT, gen_pass::new_rng, hmac::hmac_digest};
+use crate::{OtpAlgorithm, RngSource};
+
+#[derive(Debug, Clone, Copy)]
+pub struct OtpOptions {
+    pub digits: u32,
+    // TOTP 的时间步长（秒），HOTP 忽略
+    pub period: u64,
+    pub algorithm: OtpAlgorithm,
+}
+
+// 生成随机密钥并以不带填充的 base32 编码，RFC 4226 建议至少 160 bit
+pub fn process_otp_secret(bytes: usize, source: RngSource) -> String {
+    let mut secret = vec![0u8; bytes];
+    new_rng(source).fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+// 生成认证器 App 可以导入的 otpauth:// URI，指定 counter 时为 HOTP
+pub fn process_otp_uri(
+    secret: &str,
+    account: &str,
+    issuer: Option<&str>,
+    counter: Option<u64>,
+    opts: &OtpOptions,
+) -> String {
+    let encode = |s| utf8_percent_encode(s, URI_COMPONENT).to_string();
+    let label = match issuer {
+        Some(issuer) => format!("{}:{}", encode(issuer), encode(account)),
+        None => encode(account),
+    };
+
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}",
+        if counter.is_some() { "hotp" } else { "totp" },
+        label,
+        secret
+    );
+    if let Some(issuer) = issuer {
+        uri.push_str(&format!("&issuer={}", encode(issuer)));
+    }
+    uri.push_str(&format!(
+        "&algorithm={}&digits={}",
+        opts.algorithm.to_string().to_uppercase(),
+        opts.digits
+    ));
+    match counter {
+        Some(counter) => uri.push_str(&format!("&counter={}", counter)),
+        None => uri.push_str(&format!("&period={}", opts.period)),
+    }
+    uri
+}
+
+// 在终端中用 Unicode 半角方块渲染二维码
+pub fn process_otp_qr(uri: &str) -> Result<String> {
+    let code = QrCode::new(uri.as_bytes())?;
+    let image = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    Ok(image)
+}
+
+// 指定 counter 时按 HOTP 计算，否则按当前时间计算 TOTP
+pub fn process_otp_code(secret: &str, counter: Option<u64>, opts: &OtpOptions) -> Result<String> {
+    let key = decode_secret(secret)?;
+    let counter = counter.unwrap_or_else(|| totp_counter(now(), opts.period));
+    Ok(hotp(&key, counter, opts))
+}
+
+// 返回匹配的计数器偏移量，不匹配时返回 None
+// TOTP 允许前后各 window 个时间步的时钟偏差，HOTP 向后查找 window 个计数器
+pub fn process_otp_verify(
+    secret: &str,
+    code: &str,
+    counter: Option<u64>,
+    window: u64,
+    opts: &OtpOptions,
+) -> Result<Option<i64>> {
+    let key = decode_secret(secret)?;
+    let offsets = match counter {
+        Some(_) => 0..=window as i64,
+        None => -(window as i64)..=window as i64,
+    };
+    let base = counter.unwrap_or_else(|| totp_counter(now(), opts.period));
+
+    // 检查完整个窗口再返回，避免通过耗时推断匹配的位置
+    let mut matched = None;
+    for offset in offsets {
+        let Some(counter) = base.checked_add_signed(offset) else {
+            continue;
+        };
+        if bool::from(hotp(&key, counter, opts).as_bytes().ct_eq(code.as_bytes()))
+            && matched.is_none()
+        {
+            matched = Some(offset);
+        }
+    }
+    Ok(matched)
+}
+
+// 兼容小写、空格和填充字符，方便直接粘贴其他服务给出的密钥
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let secret = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid base32 secret: {}", e))
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn totp_counter(time: u64, period: u64) -> u64 {
+    time / period.max(1)
+}
+
+// RFC 4226 动态截断
+fn hotp(key: &[u8], counter: u64, opts: &OtpOptions) -> String {
+    let msg = counter.to_be_bytes();
+    let digest = match opts.algorithm {
+        OtpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(key, &msg),
+        OtpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(key, &msg),
+        OtpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(key, &msg),
+    };
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset],
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]) & 0x7fff_ffff;
+    let code = binary as u64 % 10u64.pow(opts.digits);
+    format!("{:0width$}", code, width = opts.digits as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 附录 B 的测试向量
+    #[test]
+    fn test_totp_rfc6238() {
+        let cases = [
+            (
+                OtpAlgorithm::Sha1,
+                &b"12345678901234567890"[..],
+                59,
+                "94287082",
+            ),
+            (
+                OtpAlgorithm::Sha256,
+                b"12345678901234567890123456789012",
+                1111111109,
+                "68084774",
+            ),
+            (
+                OtpAlgorithm::Sha512,
+                b"1234567890123456789012345678901234567890123456789012345678901234",
+                20000000000,
+                "47863826",
+            ),
+        ];
+        for (algorithm, key, time, expected) in cases {
+            let opts = OtpOptions {
+                digits: 8,
+                period: 30,
+                algorithm,
+            };
+            assert_eq!(hotp(key, totp_counter(time, 30), &opts), expected);
+        }
+    }
+
+    // RFC 4226 附录 D 的测试向量
+    #[test]
+    fn test_hotp_rfc4226() -> Result<()> {
+        let opts = OtpOptions {
+            digits: 6,
+            period: 30,
+            algorithm: OtpAlgorithm::Sha1,
+        };
+        let secret = BASE32_NOPAD.encode(b"12345678901234567890");
+        assert_eq!(process_otp_code(&secret, Some(0), &opts)?, "755224");
+        assert_eq!(process_otp_code(&secret, Some(9), &opts)?, "520489");
+
+        // 小写和空格分组的密钥也能识别
+        let grouped = secret.to_lowercase().replace("gez", "gez ");
+        assert_eq!(
+            process_otp_verify(&grouped, "399871", Some(6), 2, &opts)?,
+            Some(2)
+        );
+        assert_eq!(
+            process_otp_verify(&secret, "399871", Some(5), 2, &opts)?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_uri() {
+        let opts = OtpOptions {
+            digits: 6,
+            period: 30,
+            algorithm: OtpAlgorithm::Sha1,
+        };
+        let secret = process_otp_secret(20, RngSource::Thread);
+        assert_eq!(secret.len(), 32);
+
+        let uri = process_otp_uri(&secret, "alice@example.com", Some("ACME Co"), None, &opts);
+        assert_eq!(
+            uri,
+            format!(
+                "otpauth://totp/ACME%20Co:alice%40example.com?secret={}&issuer=ACME%20Co&algorithm=SHA1&digits=6&period=30",
+                secret
+            )
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use rand::{seq::SliceRandom, Rng};
+use std::fs;
+
+use super::gen_pass::new_rng;
+use crate::RngSource;
+
+// EFF 大词表，共 7776 个单词，每行格式为 "11111\tabacus"
+const EFF_LARGE_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub words: u8,
+    pub separator: String,
+    pub wordlist: Option<String>,
+    pub capitalize: bool,
+    pub digits: u8,
+    pub rng: RngSource,
+}
+
+// 返回生成的口令以及按词表大小计算的熵（bit）
+pub fn process_gen_passphrase(opts: &PassphraseOptions) -> Result<(String, f64)> {
+    let content = match &opts.wordlist {
+        Some(path) => fs::read_to_string(path)?,
+        None => EFF_LARGE_WORDLIST.to_string(),
+    };
+    let wordlist = parse_wordlist(&content);
+    if wordlist.len() < 2 {
+        anyhow::bail!("Wordlist must contain at least 2 distinct words");
+    }
+
+    let mut rng = new_rng(opts.rng);
+    let mut words = (0..opts.words)
+        .map(|_| {
+            let word = *wordlist.choose(&mut rng).expect("wordlist is empty");
+            if opts.capitalize {
+                capitalize(word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // 每个数字随机追加到某个单词后面
+    if !words.is_empty() {
+        for _ in 0..opts.digits {
+            let idx = rng.gen_range(0..words.len());
+            let digit = rng.gen_range(0..10u8);
+            words[idx].push((b'0' + digit) as char);
+        }
+    }
+
+    // 只计算单词和数字本身的熵，数字插入的位置不计入，结果偏保守
+    let entropy =
+        opts.words as f64 * (wordlist.len() as f64).log2() + opts.digits as f64 * 10f64.log2();
+
+    Ok((words.join(&opts.separator), entropy))
+}
+
+// 兼容 diceware 格式（骰子编号 + 单词）和每行一个单词的格式，去除重复单词
+fn parse_wordlist(content: &str) -> Vec<&str> {
+    let mut words = content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect::<Vec<_>>();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eff_wordlist() {
+        let words = parse_wordlist(EFF_LARGE_WORDLIST);
+        assert_eq!(words.len(), 7776);
+        assert!(words.contains(&"abacus"));
+        assert!(words.contains(&"zoom"));
+    }
+
+    #[test]
+    fn test_gen_passphrase() -> Result<()> {
+        let opts = PassphraseOptions {
+            words: 6,
+            // 词表中有 t-shirt、yo-yo 这样带 - 的单词，测试使用不会出现在词表中的分隔符
+            separator: ".".to_string(),
+            wordlist: None,
+            capitalize: true,
+            digits: 2,
+            rng: RngSource::Thread,
+        };
+        let (phrase, entropy) = process_gen_passphrase(&opts)?;
+
+        let parts = phrase.split('.').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 6);
+        assert!(parts.iter().all(|p| p.starts_with(char::is_uppercase)));
+        assert_eq!(phrase.chars().filter(char::is_ascii_digit).count(), 2);
+        // 6 个单词约 77.5 bit，加上两个数字约 6.6 bit
+        assert!((entropy - 84.19).abs() < 0.01);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use zxcvbn::zxcvbn;
+
+use super::BreachDb;
+use crate::get_reader;
+
+#[derive(Debug, Serialize)]
+pub struct PasswordReport {
+    pub line: usize,
+    // 默认只输出行号，避免密码明文出现在日志或 CI 输出中
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub score: u8,
+    pub guesses_log10: f64,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    pub crack_times: CrackTimeReport,
+    // 只有指定了泄露数据库时才会检查
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breached: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrackTimeReport {
+    pub online_throttled: String,
+    pub online_unthrottled: String,
+    pub offline_slow_hash: String,
+    pub offline_fast_hash: String,
+}
+
+// 逐行读取密码并评估强度，空行会被跳过；show_passwords 为 true 时报告中包含密码明文
+pub fn process_password_check(
+    input: &str,
+    user_inputs: &[String],
+    breach_db: Option<&str>,
+    show_passwords: bool,
+) -> Result<Vec<PasswordReport>> {
+    let reader = BufReader::new(get_reader(input)?);
+    let breach_db = breach_db.map(BreachDb::open).transpose()?;
+    let user_inputs = user_inputs.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut reports = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let password = line.trim_end_matches('\r');
+        if password.is_empty() {
+            continue;
+        }
+        let mut report = check_password(idx + 1, password, &user_inputs);
+        if let Some(db) = &breach_db {
+            report.breached = Some(db.lookup(password)?);
+        }
+        if show_passwords {
+            report.password = Some(password.to_string());
+        }
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+fn check_password(line: usize, password: &str, user_inputs: &[&str]) -> PasswordReport {
+    let estimate = zxcvbn(password, user_inputs);
+    let crack_times = estimate.crack_times();
+    let (warning, suggestions) = match estimate.feedback() {
+        Some(feedback) => (
+            feedback.warning().map(|w| w.to_string()),
+            feedback
+                .suggestions()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        ),
+        None => (None, vec![]),
+    };
+
+    PasswordReport {
+        line,
+        password: None,
+        score: estimate.score().into(),
+        guesses_log10: estimate.guesses_log10(),
+        warning,
+        suggestions,
+        crack_times: CrackTimeReport {
+            online_throttled: crack_times.online_throttling_100_per_hour().to_string(),
+            online_unthrottled: crack_times.online_no_throttling_10_per_second().to_string(),
+            offline_slow_hash: crack_times
+                .offline_slow_hashing_1e4_per_second()
+                .to_string(),
+            offline_fast_hash: crack_times
+                .offline_fast_hashing_1e10_per_second()
+                .to_string(),
+        },
+        breached: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_password_user_inputs() {
+        let weak = check_password(1, "juventus1897", &["juventus"]);
+        assert!(weak.score <= 2);
+        assert!(weak.warning.is_some() || !weak.suggestions.is_empty());
+
+        let without = check_password(1, "juventus1897", &[]);
+        assert!(weak.guesses_log10 <= without.guesses_log10);
+
+        let strong = check_password(2, "M#&]gURpz4+8)jN7B3sY", &[]);
+        assert_eq!(strong.score, 4);
+        assert_eq!(strong.crack_times.online_throttled, "centuries");
+    }
+
+    #[test]
+    fn test_password_check_hides_passwords() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("passwords.txt");
+        std::fs::write(&path, "hunter2\n\ncorrect horse battery staple\n")?;
+        let path = path.to_str().unwrap();
+
+        let reports = process_password_check(path, &[], None, false)?;
+        assert_eq!(reports.iter().map(|r| r.line).collect::<Vec<_>>(), [1, 3]);
+        assert!(reports.iter().all(|r| r.password.is_none()));
+        let json = serde_json::to_string(&reports)?;
+        assert!(!json.contains("hunter2") && !json.contains("\"password\":"));
+
+        let reports = process_password_check(path, &[], None, true)?;
+        assert_eq!(reports[0].password.as_deref(), Some("hunter2"));
+        Ok(())
+    }
+}
@@ -0,0 +1,491 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path};
+use thiserror::Error;
+
+const UPPER: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijkmnopqrstuvwxyz";
+const NUMBER: &str = "123456789";
+const SYMBOL: &str = "!@#$%^&*_";
+// 可读音模式使用的字母，去掉了 c/q/w/x/y/l/o 等容易混淆的字母
+pub const CONSONANTS: &str = "bdfghjkmnprstvz";
+pub const VOWELS: &str = "aeiu";
+// 密码长度上限，避免误输入导致生成超大字符串
+pub const MAX_LENGTH: usize = 4096;
+
+// 策略校验和生成密码时的错误，调用方可以按类型区分处理
+#[derive(Debug, Error, PartialEq)]
+pub enum PolicyError {
+    #[error("Password length must be between 1 and {MAX_LENGTH}, got {0}")]
+    InvalidLength(usize),
+    #[error("Policy must contain at least one character class")]
+    NoClasses,
+    #[error("Character class {0} is empty")]
+    EmptyClass(String),
+    #[error("Character class {0} must only contain ASCII")]
+    NonAsciiClass(String),
+    #[error("Password length {length} is shorter than the {required} required characters")]
+    TooShort { length: usize, required: usize },
+    #[error("Not enough distinct characters for a password of length {0} without repeats")]
+    NotEnoughDistinct(usize),
+    #[error("Not enough distinct characters in class {0}")]
+    ClassExhausted(String),
+    #[error("No character is allowed at the start of the password")]
+    NoLeadingChar,
+    #[error("Pronounceable passwords cannot forbid repeated characters")]
+    PronounceableNoRepeat,
+    #[error("Pronounceable passwords need letter classes with both consonants and vowels")]
+    PronounceableNoLetters,
+    #[error("Pronounceable password has {letters} letters but needs {upper} uppercase")]
+    PronounceableUpper { letters: usize, upper: usize },
+    #[error("At least two distinct characters are needed to reach an entropy target")]
+    EntropyAlphabet,
+    #[error("Entropy of {0} bits needs a password longer than {MAX_LENGTH}")]
+    EntropyUnreachable(f64),
+}
+
+// 密码策略，可以从 TOML / YAML 文件加载，例如：
+//
+// length = 16
+// no_repeat = true
+//
+// [[classes]]
+// name = "number"
+// chars = "0123456789"
+// min = 2
+//
+// [[classes]]
+// name = "symbol"
+// chars = "-_."
+// min = 2
+// leading = false
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub classes: Vec<CharClass>,
+    // 密码中不允许出现重复字符
+    #[serde(default)]
+    pub no_repeat: bool,
+    // 由辅音和元音交替组成，便于口头念出
+    #[serde(default)]
+    pub pronounceable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharClass {
+    pub name: String,
+    pub chars: String,
+    // 该类字符至少出现的次数
+    #[serde(default)]
+    pub min: usize,
+    // 是否允许作为密码的第一个字符
+    #[serde(default = "default_leading")]
+    pub leading: bool,
+}
+
+fn default_leading() -> bool {
+    true
+}
+
+impl PasswordPolicy {
+    // 对应命令行的 --no-uppercase 等参数，每个开启的类至少出现一次
+    pub fn from_flags(length: usize, upper: bool, lower: bool, number: bool, symbol: bool) -> Self {
+        let classes = [
+            (upper, "upper", UPPER),
+            (lower, "lower", LOWER),
+            (number, "number", NUMBER),
+            (symbol, "symbol", SYMBOL),
+        ]
+        .into_iter()
+        .filter(|(enabled, _, _)| *enabled)
+        .map(|(_, name, chars)| CharClass::new(name, chars, 1))
+        .collect();
+
+        Self {
+            length,
+            classes,
+            no_repeat: false,
+            pronounceable: false,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let policy = match name {
+            "default" => Self::from_flags(16, true, true, true, true),
+            // AWS IAM 允许的符号
+            "aws" => Self {
+                length: 20,
+                classes: vec![
+                    CharClass::new("upper", UPPER, 1),
+                    CharClass::new("lower", LOWER, 1),
+                    CharClass::new("number", NUMBER, 1),
+                    CharClass::new("symbol", "!@#$%^&*()_+-=[]{}|'", 1),
+                ],
+                no_repeat: false,
+                pronounceable: false,
+            },
+            // Azure AD 允许的符号，不包含空格以免复制时出错
+            "azure-ad" => Self {
+                length: 16,
+                classes: vec![
+                    CharClass::new("upper", UPPER, 1),
+                    CharClass::new("lower", LOWER, 1),
+                    CharClass::new("number", NUMBER, 1),
+                    CharClass {
+                        leading: false,
+                        ..CharClass::new("symbol", "@#$%^&*-_!+=[]{}|\\:',.?/`~\"();<>", 1)
+                    },
+                ],
+                no_repeat: false,
+                pronounceable: false,
+            },
+            "pin" => Self {
+                length: 6,
+                classes: vec![CharClass::new("number", "0123456789", 0)],
+                no_repeat: false,
+                pronounceable: false,
+            },
+            _ => return None,
+        };
+        Some(policy)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Unknown policy preset or file: {}", path.display()))?;
+        let policy = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => anyhow::bail!("Unsupported policy file: {}", path.display()),
+        };
+        Ok(policy)
+    }
+
+    // 先按预设名称查找，找不到时当作策略文件路径
+    pub fn from_name_or_path(name: &str) -> Result<Self> {
+        match Self::preset(name) {
+            Some(policy) => Ok(policy),
+            None => Self::load(name),
+        }
+    }
+
+    // 所有类字符的并集，去除重复字符
+    pub fn alphabet(&self) -> Vec<u8> {
+        let mut chars = self
+            .classes
+            .iter()
+            .flat_map(|class| class.chars.bytes())
+            .collect::<Vec<_>>();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    }
+
+    pub fn can_lead(&self, c: u8) -> bool {
+        !self
+            .classes
+            .iter()
+            .any(|class| !class.leading && class.chars.as_bytes().contains(&c))
+    }
+
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        if self.length == 0 || self.length > MAX_LENGTH {
+            return Err(PolicyError::InvalidLength(self.length));
+        }
+        if self.classes.is_empty() {
+            return Err(PolicyError::NoClasses);
+        }
+        if let Some(class) = self.classes.iter().find(|class| class.chars.is_empty()) {
+            return Err(PolicyError::EmptyClass(class.name.clone()));
+        }
+        if let Some(class) = self.classes.iter().find(|class| !class.chars.is_ascii()) {
+            return Err(PolicyError::NonAsciiClass(class.name.clone()));
+        }
+
+        let required = self.classes.iter().map(|c| c.min).sum::<usize>();
+        if required > self.length {
+            return Err(PolicyError::TooShort {
+                length: self.length,
+                required,
+            });
+        }
+
+        if self.no_repeat && self.alphabet().len() < self.length {
+            return Err(PolicyError::NotEnoughDistinct(self.length));
+        }
+
+        if self.pronounceable {
+            if self.no_repeat {
+                return Err(PolicyError::PronounceableNoRepeat);
+            }
+            let (consonants, vowels) = self.syllable_letters();
+            if consonants.is_empty() || vowels.is_empty() {
+                return Err(PolicyError::PronounceableNoLetters);
+            }
+            let upper = self.upper_min();
+            if upper > self.letters() {
+                return Err(PolicyError::PronounceableUpper {
+                    letters: self.letters(),
+                    upper,
+                });
+            }
+        }
+
+        if !self.alphabet().into_iter().any(|c| self.can_lead(c)) {
+            return Err(PolicyError::NoLeadingChar);
+        }
+
+        Ok(())
+    }
+
+    // 按字母表大小计算的熵（bit），不允许重复时按排列数计算
+    pub fn entropy(&self) -> f64 {
+        if self.pronounceable {
+            return self.pronounceable_entropy();
+        }
+
+        let size = self.alphabet().len();
+        if size == 0 {
+            return 0.0;
+        }
+        if self.no_repeat {
+            (0..self.length)
+                .map(|i| (size.saturating_sub(i).max(1) as f64).log2())
+                .sum()
+        } else {
+            self.length as f64 * (size as f64).log2()
+        }
+    }
+
+    // 可读音模式下只按音节和插入字符本身计算熵，大小写和插入位置不计入，比随机字符低很多
+    fn pronounceable_entropy(&self) -> f64 {
+        let letters = self.letters();
+        let (consonant_chars, vowel_chars) = self.syllable_letters();
+        let consonants = letters.div_ceil(2) as f64 * (consonant_chars.len() as f64).log2();
+        let vowels = (letters / 2) as f64 * (vowel_chars.len() as f64).log2();
+        let inserted = self
+            .classes
+            .iter()
+            .filter(|class| !class.is_letters())
+            .map(|class| class.min as f64 * (class.unique_len() as f64).log2())
+            .sum::<f64>();
+        consonants + vowels + inserted
+    }
+
+    // 可读音模式中非字母类（数字、符号）按最少次数插入，其余位置都是字母
+    pub fn inserted(&self) -> usize {
+        self.classes
+            .iter()
+            .filter(|class| !class.is_letters())
+            .map(|class| class.min)
+            .sum()
+    }
+
+    // 字母类中允许出现的所有字母
+    pub fn letter_chars(&self) -> Vec<u8> {
+        self.classes
+            .iter()
+            .filter(|class| class.is_letters())
+            .flat_map(|class| class.chars.bytes())
+            .filter(u8::is_ascii_alphabetic)
+            .collect()
+    }
+
+    // 可读音模式使用的辅音和元音（小写形式），只保留字母类允许其小写或大写形式的字母
+    pub fn syllable_letters(&self) -> (Vec<u8>, Vec<u8>) {
+        let letters = self.letter_chars();
+        let allowed = |c: &u8| letters.contains(c) || letters.contains(&c.to_ascii_uppercase());
+        (
+            CONSONANTS.bytes().filter(allowed).collect(),
+            VOWELS.bytes().filter(allowed).collect(),
+        )
+    }
+
+    pub fn letters(&self) -> usize {
+        self.length.saturating_sub(self.inserted())
+    }
+
+    // 包含大写字母的类要求的最少次数，可读音模式下通过把字母改成大写来满足
+    pub fn upper_min(&self) -> usize {
+        self.classes
+            .iter()
+            .filter(|class| class.chars.bytes().any(|c| c.is_ascii_uppercase()))
+            .map(|class| class.min)
+            .sum()
+    }
+
+    // 从当前长度开始逐步增加，直到熵满足要求
+    pub fn with_min_entropy(mut self, bits: f64) -> Result<Self, PolicyError> {
+        if self.alphabet().len() < 2 {
+            return Err(PolicyError::EntropyAlphabet);
+        }
+        while self.entropy() < bits {
+            if self.length >= MAX_LENGTH {
+                return Err(PolicyError::EntropyUnreachable(bits));
+            }
+            self.length += 1;
+        }
+        Ok(self)
+    }
+}
+
+impl CharClass {
+    pub fn new(name: &str, chars: &str, min: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            chars: chars.to_string(),
+            min,
+            leading: true,
+        }
+    }
+
+    pub fn is_letters(&self) -> bool {
+        self.chars.bytes().any(|c| c.is_ascii_alphabetic())
+    }
+
+    fn unique_len(&self) -> usize {
+        let mut chars = self.chars.as_bytes().to_vec();
+        chars.sort_unstable();
+        chars.dedup();
+        chars.len()
+    }
+}
+
+impl fmt::Display for PasswordPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "length={}", self.length)?;
+        for class in &self.classes {
+            write!(f, ";{}>={}", class.name, class.min)?;
+            if !class.leading {
+                write!(f, ";no_leading_{}", class.name)?;
+            }
+        }
+        if self.no_repeat {
+            write!(f, ";no_repeat")?;
+        }
+        if self.pronounceable {
+            write!(f, ";pronounceable")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_from_toml() -> Result<()> {
+        let policy: PasswordPolicy = toml::from_str(
+            r#"
+            length = 12
+            no_repeat = true
+
+            [[classes]]
+            name = "lower"
+            chars = "abcdefghijklmnopqrstuvwxyz"
+
+            [[classes]]
+            name = "symbol"
+            chars = "-_."
+            min = 2
+            leading = false
+            "#,
+        )?;
+        policy.validate()?;
+
+        assert_eq!(policy.length, 12);
+        assert_eq!(policy.classes[0].min, 0);
+        assert!(policy.classes[0].leading);
+        assert!(!policy.can_lead(b'-'));
+        assert!(policy.can_lead(b'a'));
+        assert_eq!(
+            policy.to_string(),
+            "length=12;lower>=0;symbol>=2;no_leading_symbol;no_repeat"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_validate() {
+        let mut policy = PasswordPolicy::preset("aws").unwrap();
+        assert!(policy.validate().is_ok());
+
+        policy.length = 3;
+        assert_eq!(
+            policy.validate(),
+            Err(PolicyError::TooShort {
+                length: 3,
+                required: 4
+            })
+        );
+
+        policy.length = 0;
+        assert_eq!(policy.validate(), Err(PolicyError::InvalidLength(0)));
+        policy.length = 1000;
+        assert!(policy.validate().is_ok());
+        policy.length = MAX_LENGTH + 1;
+        assert_eq!(
+            policy.validate(),
+            Err(PolicyError::InvalidLength(MAX_LENGTH + 1))
+        );
+
+        let mut pin = PasswordPolicy::preset("pin").unwrap();
+        pin.no_repeat = true;
+        pin.length = 11;
+        assert_eq!(pin.validate(), Err(PolicyError::NotEnoughDistinct(11)));
+
+        assert_eq!(
+            PasswordPolicy::from_flags(16, false, false, false, false).validate(),
+            Err(PolicyError::NoClasses)
+        );
+    }
+
+    #[test]
+    fn test_policy_pronounceable_entropy() {
+        let mut policy = PasswordPolicy::from_flags(16, true, true, true, true);
+        let random = policy.entropy();
+        policy.pronounceable = true;
+
+        // 14 个字母：7 个辅音 + 7 个元音，再加 1 个数字和 1 个符号
+        assert_eq!(policy.letters(), 14);
+        let expected = 7.0 * 15f64.log2() + 7.0 * 4f64.log2() + 9f64.log2() + 9f64.log2();
+        assert!((policy.entropy() - expected).abs() < 1e-9);
+        assert!(policy.entropy() < random);
+
+        // 不允许小写字母时 i 也不能用，大写字母中没有 I
+        let mut policy = PasswordPolicy::from_flags(16, true, false, true, true);
+        policy.pronounceable = true;
+        assert_eq!(
+            policy.syllable_letters(),
+            (CONSONANTS.bytes().collect(), b"aeu".to_vec())
+        );
+        let expected = 7.0 * 15f64.log2() + 7.0 * 3f64.log2() + 9f64.log2() + 9f64.log2();
+        assert!((policy.entropy() - expected).abs() < 1e-9);
+
+        policy.classes[0].chars = "XYZ".to_string();
+        assert_eq!(policy.validate(), Err(PolicyError::PronounceableNoLetters));
+    }
+
+    #[test]
+    fn test_policy_min_entropy() -> Result<()> {
+        let policy =
+            PasswordPolicy::from_flags(8, true, true, true, true).with_min_entropy(128.0)?;
+        // 24 + 25 + 9 + 9 = 67 个字符，每个约 6.07 bit
+        assert_eq!(policy.length, 22);
+        // 超过 255 的长度也可以满足
+        let pin = PasswordPolicy::preset("pin")
+            .unwrap()
+            .with_min_entropy(4096.0)?;
+        assert_eq!(pin.length, 1234);
+        assert_eq!(
+            PasswordPolicy::preset("pin")
+                .unwrap()
+                .with_min_entropy(1e6)
+                .unwrap_err(),
+            PolicyError::EntropyUnreachable(1e6)
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,20 @@
-use std::{fs, io::Read, path::Path, vec};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+    vec,
+};
 
-use crate::{get_reader, TextSignFormat};
+use crate::{get_reader, get_writer, CipherEncoding, RngSource, TextSignFormat};
 use anyhow::{Ok, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chacha20poly1305::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
-    ChaCha20Poly1305, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce, XChaCha20Poly1305, XNonce,
 };
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 
-use super::process_gen_pass;
+use super::{process_gen_pass, PasswordPolicy};
 
 pub trait TextSign {
     fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>>;
@@ -43,30 +48,50 @@ struct Ed25519Verifier {
     key: VerifyingKey,
 }
 
-#[derive(Debug)]
+// 加密结果的格式：魔数 + 版本号 + 24 字节随机 nonce + 密文和认证标签，
+// 魔数和版本号作为附加数据参与认证
+const ENVELOPE_MAGIC: &[u8; 4] = b"RCLI";
+const ENVELOPE_VERSION: u8 = 1;
+const ENVELOPE_HEADER_LEN: usize = 5;
+const XNONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+// 不实现 Debug，避免把密钥打印出来
 struct ChaChaPoly {
     key: Key,
-    nonce: Nonce,
 }
 
 pub fn process_text_sign(input: &str, key: &str, format: TextSignFormat) -> Result<String> {
     let mut reader = get_reader(input)?;
 
-    let signed = match format {
-        TextSignFormat::Blake3 => {
-            let signer = Black3::load(&[key])?;
-            signer.sign(&mut reader)?
-        }
-        TextSignFormat::Ed25519 => {
-            let signer = Ed25519Signer::load(&[key])?;
-            signer.sign(&mut reader)?
-        }
-        _ => Err(anyhow::anyhow!("Unsupported format"))?,
-    };
+    let signed = load_signer(key, format)?.sign(&mut reader)?;
     let signed = URL_SAFE_NO_PAD.encode(&signed);
     Ok(signed)
 }
 
+// 转换管道中使用，输入已经读到内存中，返回原始的签名字节
+pub fn process_text_sign_bytes(data: &[u8], key: &str, format: TextSignFormat) -> Result<Vec<u8>> {
+    load_signer(key, format)?.sign(&mut &data[..])
+}
+
+// 输入输出都是原始字节，需要文本时在后面接 base64-encode
+pub fn process_text_encrypt_bytes(data: &[u8], key: &str) -> Result<Vec<u8>> {
+    ChaChaPoly::load(&[key])?.encrypt(data)
+}
+
+pub fn process_text_decrypt_bytes(data: &[u8], key: &str) -> Result<Vec<u8>> {
+    ChaChaPoly::load(&[key])?.decrypt(data)
+}
+
+fn load_signer(key: &str, format: TextSignFormat) -> Result<Box<dyn TextSign>> {
+    let signer: Box<dyn TextSign> = match format {
+        TextSignFormat::Blake3 => Box::new(Black3::load(&[key])?),
+        TextSignFormat::Ed25519 => Box::new(Ed25519Signer::load(&[key])?),
+        _ => Err(anyhow::anyhow!("Unsupported format"))?,
+    };
+    Ok(signer)
+}
+
 pub fn process_text_verify(
     input: &str,
     key: &str,
@@ -98,19 +123,53 @@ pub fn process_text_generate(format: TextSignFormat) -> Result<Vec<Vec<u8>>> {
     }
 }
 
-pub fn process_text_encrypt(key: &str, nonce: &str) -> Result<String> {
-    let reader = std::io::stdin();
-    let cipher = ChaChaPoly::load(&[key, nonce])?;
+// 输入可以是任意二进制数据，密文按 encoding 输出为 base64 文本或原始字节
+pub fn process_text_encrypt(
+    input: &str,
+    output: &str,
+    key: &str,
+    encoding: CipherEncoding,
+) -> Result<()> {
+    let reader = get_reader(input)?;
+    let cipher = ChaChaPoly::load(&[key])?;
     let cipher_text = cipher.encrypt(reader)?;
 
-    Ok(cipher_text)
+    let mut writer = get_writer(output)?;
+    match encoding {
+        CipherEncoding::Base64 => writeln!(writer, "{}", URL_SAFE_NO_PAD.encode(&cipher_text))?,
+        CipherEncoding::Raw => writer.write_all(&cipher_text)?,
+    }
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_text_decrypt(key: &str, nonce: &str) -> Result<String> {
-    let reader = std::io::stdin();
-    let cipher = ChaChaPoly::load(&[key, nonce])?;
-    let cipher_text = cipher.decrypt(reader)?;
-    Ok(cipher_text)
+// 解密结果按原始字节写出，不要求是 UTF-8 文本
+// 指定 legacy_nonce 时按旧版本的格式解密：没有信封，使用固定的 12 字节 nonce
+pub fn process_text_decrypt(
+    input: &str,
+    output: &str,
+    key: &str,
+    legacy_nonce: Option<&str>,
+    encoding: CipherEncoding,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut cipher_text = Vec::new();
+    reader.read_to_end(&mut cipher_text)?;
+    if let CipherEncoding::Base64 = encoding {
+        let text = std::str::from_utf8(&cipher_text)?;
+        cipher_text = URL_SAFE_NO_PAD.decode(text.trim())?;
+    }
+
+    let cipher = ChaChaPoly::load(&[key])?;
+    let plain_text = match legacy_nonce {
+        Some(nonce) => cipher.decrypt_legacy(&fs::read(nonce)?, &cipher_text)?,
+        None => cipher.decrypt(cipher_text.as_slice())?,
+    };
+
+    let mut writer = get_writer(output)?;
+    writer.write_all(&plain_text)?;
+    writer.flush()?;
+    Ok(())
 }
 
 impl TextSign for Black3 {
@@ -172,14 +231,14 @@ impl KeyLoader for Ed25519Verifier {
 impl KeyLoader for ChaChaPoly {
     fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
         let key = fs::read(&paths[0])?;
-        let nonce = fs::read(&paths[1])?;
-        Self::try_new(&key, &nonce)
+        Self::try_new(&key)
     }
 }
 
 impl KeyGenerator for Black3 {
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = process_gen_pass(32, true, true, true, true)?;
+        let policy = PasswordPolicy::from_flags(32, true, true, true, true);
+        let key = process_gen_pass(&policy, RngSource::Os)?;
         let key = key.as_bytes().to_vec();
         Ok(vec![key])
     }
@@ -197,10 +256,10 @@ impl KeyGenerator for Ed25519Signer {
 
 impl KeyGenerator for ChaChaPoly {
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
-        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        // nonce 在每次加密时随机生成，这里只生成密钥
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
 
-        Ok(vec![key.to_vec(), nonce.to_vec()])
+        Ok(vec![key.to_vec()])
     }
 }
 
@@ -210,8 +269,11 @@ impl Black3 {
     }
 
     pub fn try_new(key: &[u8]) -> Result<Self> {
-        let key = &key[..32];
-        let key = key.try_into().unwrap();
+        // 密钥文件长度不对时返回错误，而不是在切片时 panic
+        if key.len() != 32 {
+            anyhow::bail!("Blake3 key must be 32 bytes, got {}", key.len());
+        }
+        let key = key.try_into()?;
         let signer = Black3::new(key);
 
         Ok(signer)
@@ -247,44 +309,97 @@ impl Ed25519Verifier {
 }
 
 impl ChaChaPoly {
-    pub fn new(key: Key, nonce: Nonce) -> Self {
-        Self { key, nonce }
+    pub fn new(key: Key) -> Self {
+        Self { key }
     }
 
-    pub fn try_new(key: &[u8], nonce: &[u8]) -> Result<Self> {
+    pub fn try_new(key: &[u8]) -> Result<Self> {
         let key: &[u8; 32] = key.try_into()?;
-        let nonce: &[u8; 12] = nonce.try_into()?;
         let key = *Key::from_slice(key);
-        let nonce = *Nonce::from_slice(nonce);
 
-        let cipher = ChaChaPoly::new(key, nonce);
+        let cipher = ChaChaPoly::new(key);
         Ok(cipher)
     }
 
-    pub fn encrypt(&self, mut reader: impl Read) -> Result<String> {
+    pub fn encrypt(&self, mut reader: impl Read) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
 
-        let cipher = ChaCha20Poly1305::new(&self.key);
-        let cipher_text = cipher.encrypt(&self.nonce, buf.as_ref()).unwrap();
-        let cipher_text = URL_SAFE_NO_PAD.encode(&cipher_text);
-
-        Ok(cipher_text)
+        // 24 字节的 nonce 足够长，随机生成也不用担心重复
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let header = envelope_header();
+        let cipher = XChaCha20Poly1305::new(&self.key);
+        let cipher_text = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &buf,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut envelope = Vec::with_capacity(header.len() + nonce.len() + cipher_text.len());
+        envelope.extend_from_slice(&header);
+        envelope.extend_from_slice(&nonce);
+        envelope.extend_from_slice(&cipher_text);
+        Ok(envelope)
     }
 
-    pub fn decrypt(&self, mut reader: impl Read) -> Result<String> {
-        let mut buf = String::new();
-        reader.read_to_string(&mut buf)?;
-        let buf = URL_SAFE_NO_PAD.decode(buf.trim())?;
+    pub fn decrypt(&self, mut reader: impl Read) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        if buf.len() < ENVELOPE_HEADER_LEN + XNONCE_LEN + TAG_LEN
+            || &buf[..ENVELOPE_MAGIC.len()] != ENVELOPE_MAGIC
+        {
+            anyhow::bail!(
+                "Not an rcli encrypted message, use --nonce for data encrypted by older versions"
+            );
+        }
+        let (header, rest) = buf.split_at(ENVELOPE_HEADER_LEN);
+        if header[ENVELOPE_MAGIC.len()] != ENVELOPE_VERSION {
+            anyhow::bail!(
+                "Unsupported envelope version {}",
+                header[ENVELOPE_MAGIC.len()]
+            );
+        }
+        let (nonce, cipher_text) = rest.split_at(XNONCE_LEN);
+
+        let cipher = XChaCha20Poly1305::new(&self.key);
+        // 认证失败说明密钥不对或者密文被篡改
+        let plain_text = cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: cipher_text,
+                    aad: header,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or corrupted data"))?;
+
+        Ok(plain_text)
+    }
 
+    // 旧版本使用固定 nonce 的 ChaCha20-Poly1305，只保留解密用于迁移已有数据
+    pub fn decrypt_legacy(&self, nonce: &[u8], cipher_text: &[u8]) -> Result<Vec<u8>> {
+        let nonce: &[u8; 12] = nonce.try_into()?;
         let cipher = ChaCha20Poly1305::new(&self.key);
-        let plain_text = cipher.decrypt(&self.nonce, buf.as_ref()).unwrap();
-        let plain_text = String::from_utf8(plain_text)?;
+        let plain_text = cipher
+            .decrypt(Nonce::from_slice(nonce), cipher_text)
+            .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key/nonce or corrupted data"))?;
 
         Ok(plain_text)
     }
 }
 
+fn envelope_header() -> [u8; ENVELOPE_HEADER_LEN] {
+    let mut header = [0u8; ENVELOPE_HEADER_LEN];
+    header[..ENVELOPE_MAGIC.len()].copy_from_slice(ENVELOPE_MAGIC);
+    header[ENVELOPE_MAGIC.len()] = ENVELOPE_VERSION;
+    header
+}
+
 #[cfg(test)]
 mod tests {
     use crate::process::text::{KeyLoader, TextVerify};
@@ -316,16 +431,77 @@ mod tests {
 
     #[test]
     fn test_chacha_poly_encrypt_decrypt() -> Result<(), anyhow::Error> {
-        let cipher =
-            super::ChaChaPoly::load(&["fixtures/chachaPoly.key", "fixtures/chachaPoly.nonce"])?;
-
-        println!("{:?}", cipher);
+        let cipher = super::ChaChaPoly::load(&["fixtures/chachaPoly.key"])?;
 
         let data = b"Hello World";
         let cipher_text = cipher.encrypt(&mut &data[..])?;
-        let plain_text = cipher.decrypt(cipher_text.as_bytes())?;
+        let plain_text = cipher.decrypt(cipher_text.as_slice())?;
+        assert_eq!(data, plain_text.as_slice());
+
+        // 每次加密使用不同的 nonce，相同明文得到不同密文
+        let again = cipher.encrypt(&mut &data[..])?;
+        assert_ne!(cipher_text, again);
+        assert_eq!(&cipher_text[..5], b"RCLI\x01");
+        assert_eq!(cipher_text.len(), 5 + 24 + data.len() + 16);
+
+        // 修改版本号会导致解密失败
+        let mut tampered = again.clone();
+        tampered[4] = 2;
+        assert!(cipher.decrypt(tampered.as_slice()).is_err());
+        Ok(())
+    }
 
-        assert_eq!(data, plain_text.as_bytes());
+    #[test]
+    fn test_chacha_poly_decrypt_legacy() -> Result<(), anyhow::Error> {
+        use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+        use std::fs;
+
+        let cipher = super::ChaChaPoly::load(&["fixtures/chachaPoly.key"])?;
+        let nonce = fs::read("fixtures/chachaPoly.nonce")?;
+        let legacy = ChaCha20Poly1305::new(&cipher.key)
+            .encrypt(Nonce::from_slice(&nonce), b"Hello World".as_ref())
+            .unwrap();
+
+        assert!(cipher.decrypt(legacy.as_slice()).is_err());
+        assert_eq!(cipher.decrypt_legacy(&nonce, &legacy)?, b"Hello World");
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_encrypt_decrypt_files() -> Result<(), anyhow::Error> {
+        use super::{process_text_decrypt, process_text_encrypt, CipherEncoding};
+        use std::fs;
+
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let key = "fixtures/chachaPoly.key";
+        // 不是合法 UTF-8 的二进制数据
+        let data = (0..=255u8).cycle().take(10_000).collect::<Vec<_>>();
+        fs::write(path("data.bin"), &data)?;
+
+        for encoding in [CipherEncoding::Base64, CipherEncoding::Raw] {
+            process_text_encrypt(&path("data.bin"), &path("data.enc"), key, encoding)?;
+            process_text_decrypt(&path("data.enc"), &path("data.out"), key, None, encoding)?;
+            assert_eq!(fs::read(path("data.out"))?, data);
+        }
+        // 信封头、nonce 和 16 字节的认证标签
+        assert_eq!(
+            fs::metadata(path("data.enc"))?.len(),
+            data.len() as u64 + 5 + 24 + 16
+        );
+
+        // 密文被篡改时返回错误而不是 panic
+        let mut tampered = fs::read(path("data.enc"))?;
+        tampered[40] ^= 1;
+        fs::write(path("data.enc"), tampered)?;
+        let ret = process_text_decrypt(
+            &path("data.enc"),
+            &path("data.out"),
+            key,
+            None,
+            CipherEncoding::Raw,
+        );
+        assert!(ret.is_err());
         Ok(())
     }
 }
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use base64::Engine as _;
+use rand::{seq::SliceRandom, RngCore};
+
+use super::{b64::engine, gen_pass::new_rng};
+use crate::{Base64Format, RngSource, TokenAlphabet};
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+// 去掉了 0/O/I/l，和比特币地址使用的字符表一致
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const HEX: &[u8] = b"0123456789abcdef";
+
+#[derive(Debug, Clone)]
+pub struct TokenOptions {
+    pub prefix: String,
+    // 随机部分的字符数，不含前缀和校验和
+    pub length: usize,
+    pub alphabet: TokenAlphabet,
+    pub rng: RngSource,
+}
+
+// 令牌格式为 前缀 + 随机部分 + 定长校验和，校验和是前缀和随机部分的 CRC32，
+// 可以离线识别复制错误或随手编造的令牌，类似 GitHub 的 ghp_ 令牌
+// 返回生成的令牌以及随机部分的熵（bit）
+pub fn process_token_generate(opts: &TokenOptions) -> Result<(String, f64)> {
+    if opts.prefix.chars().any(char::is_whitespace) {
+        anyhow::bail!("Token prefix must not contain whitespace");
+    }
+
+    let mut rng = new_rng(opts.rng);
+    let random = match opts.alphabet {
+        TokenAlphabet::Base64Url => {
+            // 每 3 个字节编码为 4 个字符，多生成的部分截断
+            let mut bytes = vec![0u8; opts.length.div_ceil(4) * 3];
+            rng.fill_bytes(&mut bytes);
+            let mut encoded = engine(Base64Format::UrlSafe).encode(&bytes);
+            encoded.truncate(opts.length);
+            encoded
+        }
+        alphabet => {
+            let chars = alphabet_chars(alphabet);
+            (0..opts.length)
+                .map(|_| *chars.choose(&mut rng).expect("alphabet is empty") as char)
+                .collect()
+        }
+    };
+
+    let body = format!("{}{}", opts.prefix, random);
+    let checksum = encode_checksum(crc32fast::hash(body.as_bytes()), opts.alphabet);
+    let entropy = opts.length as f64 * (alphabet_len(opts.alphabet) as f64).log2();
+
+    Ok((body + &checksum, entropy))
+}
+
+// 只校验末尾的校验和，不需要知道前缀和随机部分的长度
+pub fn process_token_verify(token: &str, alphabet: TokenAlphabet) -> Result<bool> {
+    let token = token.trim();
+    let width = checksum_width(alphabet);
+    if !token.is_ascii() || token.len() <= width {
+        anyhow::bail!("Token is too short or contains non-ASCII characters");
+    }
+
+    let (body, checksum) = token.split_at(token.len() - width);
+    let expected = encode_checksum(crc32fast::hash(body.as_bytes()), alphabet);
+    Ok(expected == checksum)
+}
+
+fn alphabet_chars(alphabet: TokenAlphabet) -> &'static [u8] {
+    match alphabet {
+        TokenAlphabet::Base62 => BASE62,
+        TokenAlphabet::Base58 => BASE58,
+        TokenAlphabet::Hex => HEX,
+        TokenAlphabet::Base64Url => {
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+        }
+    }
+}
+
+fn alphabet_len(alphabet: TokenAlphabet) -> usize {
+    alphabet_chars(alphabet).len()
+}
+
+// 能完整表示 32 bit 校验和的最少字符数
+fn checksum_width(alphabet: TokenAlphabet) -> usize {
+    match alphabet {
+        TokenAlphabet::Hex => 8,
+        _ => 6,
+    }
+}
+
+fn encode_checksum(crc: u32, alphabet: TokenAlphabet) -> String {
+    match alphabet {
+        TokenAlphabet::Hex => format!("{:08x}", crc),
+        TokenAlphabet::Base64Url => engine(Base64Format::UrlSafe).encode(crc.to_be_bytes()),
+        TokenAlphabet::Base62 | TokenAlphabet::Base58 => {
+            // 按进制转换后左侧补齐，保证校验和定长
+            let chars = alphabet_chars(alphabet);
+            let base = chars.len() as u64;
+            let mut n = crc as u64;
+            let mut out = vec![chars[0]; checksum_width(alphabet)];
+            for c in out.iter_mut().rev() {
+                *c = chars[(n % base) as usize];
+                n /= base;
+            }
+            String::from_utf8(out).expect("alphabet is ASCII")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_roundtrip() -> Result<()> {
+        for alphabet in [
+            TokenAlphabet::Base62,
+            TokenAlphabet::Base58,
+            TokenAlphabet::Hex,
+            TokenAlphabet::Base64Url,
+        ] {
+            let opts = TokenOptions {
+                prefix: "rcli_live_".to_string(),
+                length: 30,
+                alphabet,
+                rng: RngSource::Thread,
+            };
+            let (token, _) = process_token_generate(&opts)?;
+            assert!(token.starts_with("rcli_live_"));
+            assert_eq!(token.len(), 10 + 30 + checksum_width(alphabet));
+            assert!(process_token_verify(&token, alphabet)?);
+
+            // 修改任意一个字符都会导致校验失败
+            let mut tampered = token.into_bytes();
+            tampered[12] = if tampered[12] == b'a' { b'b' } else { b'a' };
+            let tampered = String::from_utf8(tampered)?;
+            assert!(!process_token_verify(&tampered, alphabet)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_checksum() -> Result<()> {
+        // CRC32("123456789") = 0xCBF43926
+        assert!(process_token_verify(
+            "123456789cbf43926",
+            TokenAlphabet::Hex
+        )?);
+        assert_eq!(encode_checksum(0, TokenAlphabet::Base62), "000000");
+        assert_eq!(encode_checksum(u32::MAX, TokenAlphabet::Base62), "4gfFC3");
+        assert!(process_token_verify("short", TokenAlphabet::Base62).is_err());
+        Ok(())
+    }
+}
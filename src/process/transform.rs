@@ -0,0 +1,264 @@
+use anyhow::Result;
+use flate2::{
+    write::{GzEncoder, MultiGzDecoder},
+    Compression,
+};
+use std::io::{self, Write};
+
+use super::{
+    b64::base64_stage,
+    codec::{new_decoder, new_encoder, pump, StreamCodec},
+    hash::Hasher,
+    text::{process_text_decrypt_bytes, process_text_encrypt_bytes, process_text_sign_bytes},
+};
+use crate::{get_reader, get_writer, Stage};
+
+const ZSTD_LEVEL: i32 = 3;
+
+// 依次经过每个阶段，前一个阶段的输出是后一个阶段的输入
+pub fn process_transform(input: &str, output: &str, stages: &[Stage]) -> Result<u64> {
+    let mut chain = Chain(stages.iter().map(new_stage).collect::<Result<Vec<_>>>()?);
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+
+    let size = pump(&mut reader, &mut writer, &mut chain)?;
+    writer.flush()?;
+
+    Ok(size)
+}
+
+fn new_stage(stage: &Stage) -> Result<Box<dyn StreamCodec>> {
+    let stage: Box<dyn StreamCodec> = match stage {
+        Stage::Encode(codec, separator) => new_encoder(*codec, separator),
+        Stage::Decode(codec) => new_decoder(*codec),
+        Stage::Base64Encode(format) => base64_stage(*format, false)?,
+        Stage::Base64Decode(format) => base64_stage(*format, true)?,
+        Stage::Gzip => Box::new(WriteStage {
+            writer: Some(GzEncoder::new(Vec::new(), Compression::default())),
+            output: |w| w.get_mut(),
+            finish: |w| w.finish(),
+        }),
+        Stage::Gunzip => Box::new(WriteStage {
+            writer: Some(MultiGzDecoder::new(Vec::new())),
+            output: |w| w.get_mut(),
+            finish: |w| w.finish(),
+        }),
+        Stage::Zstd => Box::new(WriteStage {
+            writer: Some(zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL)?),
+            output: |w| w.get_mut(),
+            finish: |w| w.finish(),
+        }),
+        Stage::Unzstd => Box::new(WriteStage {
+            writer: Some(zstd::stream::write::Decoder::new(Vec::new())?),
+            output: |w| w.get_mut(),
+            finish: |mut w| {
+                w.flush()?;
+                Ok(w.into_inner())
+            },
+        }),
+        Stage::Hash(algorithm) => Box::new(HashStage(Some(Hasher::new(*algorithm)))),
+        Stage::Sign(format, key) => {
+            let (format, key) = (*format, key.clone());
+            Box::new(Buffered::new(move |data| {
+                process_text_sign_bytes(data, &key, format)
+            }))
+        }
+        Stage::Encrypt(key) => {
+            let key = key.clone();
+            Box::new(Buffered::new(move |data| {
+                process_text_encrypt_bytes(data, &key)
+            }))
+        }
+        Stage::Decrypt(key) => {
+            let key = key.clone();
+            Box::new(Buffered::new(move |data| {
+                process_text_decrypt_bytes(data, &key)
+            }))
+        }
+    };
+    Ok(stage)
+}
+
+struct Chain(Vec<Box<dyn StreamCodec>>);
+
+impl StreamCodec for Chain {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let mut data = input.to_vec();
+        for stage in self.0.iter_mut() {
+            let mut next = Vec::new();
+            stage.update(&data, &mut next)?;
+            data = next;
+        }
+        out.extend(data);
+        Ok(())
+    }
+
+    // 前一个阶段结束时输出的数据要先交给后一个阶段处理，再结束后一个阶段
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        let mut data = Vec::new();
+        for stage in self.0.iter_mut() {
+            let mut next = Vec::new();
+            stage.update(&data, &mut next)?;
+            stage.finish(&mut next)?;
+            data = next;
+        }
+        out.extend(data);
+        Ok(())
+    }
+}
+
+// 把基于 Write 的压缩/解压器适配为 StreamCodec，每次写入后取走内部缓冲区中已经产生的数据
+struct WriteStage<W: Write> {
+    writer: Option<W>,
+    output: fn(&mut W) -> &mut Vec<u8>,
+    finish: fn(W) -> io::Result<Vec<u8>>,
+}
+
+impl<W: Write> StreamCodec for WriteStage<W> {
+    fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let writer = self.writer.as_mut().expect("stage already finished");
+        writer.write_all(input)?;
+        out.append((self.output)(writer));
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            out.extend((self.finish)(writer)?);
+        }
+        Ok(())
+    }
+}
+
+// 输出原始的摘要字节，通常后面接 hex-encode
+struct HashStage(Option<Hasher>);
+
+impl StreamCodec for HashStage {
+    fn update(&mut self, input: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+        if let Some(hasher) = &mut self.0 {
+            hasher.update(input);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if let Some(hasher) = self.0.take() {
+            out.extend(hasher.finalize());
+        }
+        Ok(())
+    }
+}
+
+// 签名和加密需要完整的输入，读完后一次性处理
+type BufferedFn = Box<dyn FnMut(&[u8]) -> Result<Vec<u8>>>;
+
+struct Buffered {
+    buf: Vec<u8>,
+    apply: BufferedFn,
+}
+
+impl Buffered {
+    fn new(apply: impl FnMut(&[u8]) -> Result<Vec<u8>> + 'static) -> Self {
+        Self {
+            buf: Vec::new(),
+            apply: Box::new(apply),
+        }
+    }
+}
+
+impl StreamCodec for Buffered {
+    fn update(&mut self, input: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+        self.buf.extend_from_slice(input);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        let data = std::mem::take(&mut self.buf);
+        out.extend((self.apply)(&data)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Base64Format, Codec, HashAlgorithm, TextSignFormat};
+    use sha2::{Digest, Sha256};
+    use std::fs;
+
+    fn run(stages: &[Stage], input: &[u8]) -> Result<Vec<u8>> {
+        let dir = tempfile::tempdir()?;
+        let (src, dst) = (dir.path().join("in"), dir.path().join("out"));
+        fs::write(&src, input)?;
+        process_transform(src.to_str().unwrap(), dst.to_str().unwrap(), stages)?;
+        Ok(fs::read(dst)?)
+    }
+
+    #[test]
+    fn test_transform_roundtrip_chain() -> Result<()> {
+        let data = (0..200_000u32)
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<_>>();
+        let stages = [
+            Stage::Gzip,
+            Stage::Base64Encode(Base64Format::Mime),
+            Stage::Base64Decode(Base64Format::Auto),
+            Stage::Gunzip,
+            Stage::Zstd,
+            Stage::Encode(Codec::Hex, String::new()),
+            Stage::Decode(Codec::Hex),
+            Stage::Unzstd,
+            Stage::Hash(HashAlgorithm::Sha256),
+            Stage::Encode(Codec::Hex, String::new()),
+        ];
+        let expected = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(String::from_utf8(run(&stages, &data)?)?, expected);
+
+        // 空的管道原样输出
+        assert_eq!(run(&[], b"rcli")?, b"rcli");
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_text_stages() -> Result<()> {
+        let sign = [
+            Stage::Sign(TextSignFormat::Blake3, "fixtures/blake3.txt".to_string()),
+            Stage::Base64Encode(Base64Format::UrlSafe),
+        ];
+        let expected = crate::process_text_sign(
+            "fixtures/b64_encode.txt",
+            "fixtures/blake3.txt",
+            TextSignFormat::Blake3,
+        )?;
+        assert_eq!(
+            String::from_utf8(run(&sign, &fs::read("fixtures/b64_encode.txt")?)?)?,
+            expected
+        );
+
+        let key = "fixtures/chachaPoly.key".to_string();
+        let stages = [Stage::Encrypt(key.clone()), Stage::Decrypt(key)];
+        assert_eq!(run(&stages, b"Hello World")?, b"Hello World");
+
+        // 二进制明文原样输出，密钥不对时返回错误而不是 panic
+        let data = [0xffu8, 0x00, 0xfe];
+        assert_eq!(run(&stages, &data)?, data);
+        let wrong = [
+            Stage::Encrypt("fixtures/chachaPoly.key".to_string()),
+            Stage::Decrypt("fixtures/blake3.txt".to_string()),
+        ];
+        assert!(run(&wrong, b"Hello World").is_err());
+
+        let dir = tempfile::tempdir()?;
+        let short = dir.path().join("short.key");
+        fs::write(&short, "abc")?;
+        let sign = [Stage::Sign(
+            TextSignFormat::Blake3,
+            short.to_str().unwrap().to_string(),
+        )];
+        assert!(run(&sign, b"Hello World").is_err());
+        Ok(())
+    }
+}
@@ -1,17 +1,29 @@
 use anyhow::Result;
-use std::{fs::File, io::Read};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+};
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     if input == "-" {
         Ok(Box::new(std::io::stdin()))
     } else {
-        Ok(Box::new(File::open(input).unwrap()))
+        Ok(Box::new(File::open(input)?))
+    }
+}
+
+// "-" 表示写到标准输出，stdout
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    if output == "-" {
+        Ok(Box::new(std::io::stdout().lock()))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(output)?)))
     }
 }
 
 pub fn read_data(input: &str) -> Result<String> {
     let mut reader = get_reader(input)?;
     let mut buffer = String::new();
-    let _ = reader.read_to_string(&mut buffer);
+    reader.read_to_string(&mut buffer)?;
     Ok(buffer)
 }